@@ -0,0 +1,26 @@
+use std::env;
+use std::process::Command;
+
+// Records the compiler version and git commit for the run metadata of JSON output.
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = run(&rustc, &["--version"]);
+    let commit = run("git", &["rev-parse", "HEAD"]);
+
+    println!("cargo:rustc-env=HASHING_RUSTC_VERSION={}", version);
+    println!("cargo:rustc-env=HASHING_GIT_COMMIT={}", commit);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+}
+
+fn run(program: &str, args: &[&str]) -> String {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
@@ -0,0 +1,54 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate hashing_fuzz;
+
+use hashing_fuzz::imp::{Bits, PairShiftU64D32};
+use hashing_fuzz::rng::SplitMix64;
+use hashing_fuzz::{pair_shift, Op};
+
+fuzz_target!(|data: &[u8]| {
+    let (seed, ops) = hashing_fuzz::decode(data, 32);
+    let mut rng = SplitMix64::new(seed);
+    let mut a = [0; 65];
+    rng.fill_u64(&mut a);
+
+    // h is reused across finishes, fresh is new for each message.
+    let mut h = PairShiftU64D32::new(a);
+    let mut fresh = PairShiftU64D32::new(a);
+    let mut xs = Vec::new();
+    for op in ops {
+        match op {
+            // At most d = 32 words per message; in debug builds a write past
+            // them panics and leaves the state as it was.
+            Op::Write(x) => {
+                if xs.len() == 32 {
+                    assert!(h.is_done());
+                    if cfg!(debug_assertions) {
+                        assert!(hashing_fuzz::panics(|| h.write_u64(x)));
+                    }
+                } else {
+                    assert!(!h.is_done());
+                    h.write_u64(x);
+                    fresh.write_u64(x);
+                    xs.push(x);
+                }
+            }
+            // Finishing also resets, so a second finish hashes the empty message.
+            Op::Finish(l) => {
+                let expected = pair_shift(&a, l, &xs);
+                let bits = Bits::new(l).unwrap();
+                assert_eq!(h.finish(bits), expected, "{:?}", xs);
+                assert_eq!(fresh.finish(bits), expected, "{:?}", xs);
+                assert_eq!(h.finish(bits), pair_shift(&a, l, &[]));
+                fresh = PairShiftU64D32::new(a);
+                xs.clear();
+            }
+            Op::Reset => {
+                h.reset();
+                fresh = PairShiftU64D32::new(a);
+                xs.clear();
+            }
+        }
+    }
+});
@@ -0,0 +1,51 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate hashing_fuzz;
+
+use hashing_fuzz::imp::{Bits, PreprocPolyU64D32};
+use hashing_fuzz::rng::SplitMix64;
+use hashing_fuzz::{preproc_poly, Op};
+
+fuzz_target!(|data: &[u8]| {
+    let (seed, ops) = hashing_fuzz::decode(data, 64);
+    let mut rng = SplitMix64::new(seed);
+    let mut prep1 = [0; 65];
+    let mut prep2 = [0; 65];
+    rng.fill_u64(&mut prep1);
+    rng.fill_u64(&mut prep2);
+    let a = rng.below_m89();
+    let b = rng.below_m89();
+    let c = rng.below_m89();
+
+    // h is reused across finishes, fresh is new for each message. Messages of any
+    // length are split into blocks of d = 32 words.
+    let mut h = PreprocPolyU64D32::new(prep1, prep2, a, b, c);
+    let mut fresh = PreprocPolyU64D32::new(prep1, prep2, a, b, c);
+    let mut xs = Vec::new();
+    for op in ops {
+        match op {
+            Op::Write(x) => {
+                h.write_u64(x);
+                fresh.write_u64(x);
+                xs.push(x);
+            }
+            // Finishing also resets, so a second finish hashes the empty message.
+            Op::Finish(l) => {
+                let expected = preproc_poly((&prep1, &prep2), a, b, c, l, &xs);
+                let bits = Bits::new(l).unwrap();
+                assert_eq!(h.finish(bits), expected, "{:?}", xs);
+                assert_eq!(fresh.finish(bits), expected, "{:?}", xs);
+                let empty = preproc_poly((&prep1, &prep2), a, b, c, l, &[]);
+                assert_eq!(h.finish(bits), empty);
+                fresh = PreprocPolyU64D32::new(prep1, prep2, a, b, c);
+                xs.clear();
+            }
+            Op::Reset => {
+                h.reset();
+                fresh = PreprocPolyU64D32::new(prep1, prep2, a, b, c);
+                xs.clear();
+            }
+        }
+    }
+});
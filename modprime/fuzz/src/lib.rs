@@ -0,0 +1,113 @@
+// The crate under test is a binary, so include the modules the targets need.
+#[path = "../../src/imp.rs"]
+pub mod imp;
+#[path = "../../src/rng.rs"]
+pub mod rng;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use imp::{Bits, FieldElem89, PolyU64};
+
+////////////////////////////////////////
+// Operations
+////////////////////////////////////////
+
+// Description: Decodes fuzzer input into a key seed and a sequence of calls on a
+// streaming hash. The first 8 bytes seed the key; after them each byte selects
+// an operation: a finish with 1 to max_bits output bits from the next byte, a
+// reset, or a write of a word of 1 to 8 next bytes, big-endian.
+
+#[derive(Clone, Copy, Debug)]
+pub enum Op {
+    Write(u64),
+    Finish(usize),
+    Reset,
+}
+
+pub fn decode(data: &[u8], max_bits: usize) -> (u64, Vec<Op>) {
+    let mut bytes = data.iter().cloned();
+    let seed = bytes
+        .by_ref()
+        .take(8)
+        .fold(0, |seed, byte| seed << 8 | u64::from(byte));
+
+    let mut ops = Vec::new();
+    while let Some(op) = bytes.next() {
+        match op & 7 {
+            0 => {
+                let l = bytes.next().unwrap_or(0) as usize % max_bits + 1;
+                ops.push(Op::Finish(l));
+            }
+            1 => ops.push(Op::Reset),
+            _ => {
+                let len = (op >> 3) as usize % 8 + 1;
+                let x = bytes
+                    .by_ref()
+                    .take(len)
+                    .fold(0, |x, byte| x << 8 | u64::from(byte));
+                ops.push(Op::Write(x));
+            }
+        }
+    }
+    (seed, ops)
+}
+
+// Whether f panics. The panic hook of libfuzzer-sys aborts, so it is replaced
+// by a silent one for the call; this needs the default unwinding panics.
+pub fn panics<F: FnOnce()>(f: F) -> bool {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(hook);
+    result.is_err()
+}
+
+////////////////////////////////////////
+// One-Shot References
+////////////////////////////////////////
+
+// Description: Each hash of a whole message at once, from its definition, to
+// check the state machines of imp against.
+
+// Sum of a[i] x_i over the message plus a[n], top l bits.
+pub fn vector_shift(a: &[u64; 65], l: usize, xs: &[u32]) -> u32 {
+    let sum = xs.iter().zip(a.iter()).fold(a[xs.len()], |sum, (&x, &a)| {
+        sum.wrapping_add(a.wrapping_mul(u64::from(x)))
+    });
+    (sum >> (64 - l)) as u32
+}
+
+// Sum of (a[2i] + hi(x_i)) (a[2i+1] + lo(x_i)) over the message plus a[2n], top l
+// bits.
+pub fn pair_shift(a: &[u64; 65], l: usize, xs: &[u64]) -> u32 {
+    let sum = xs.iter().enumerate().fold(a[2 * xs.len()], |sum, (i, &x)| {
+        let f0 = a[2 * i].wrapping_add(x >> 32);
+        let f1 = a[2 * i + 1].wrapping_add(x & 0xffffffff);
+        sum.wrapping_add(f0.wrapping_mul(f1))
+    });
+    (sum >> (64 - l)) as u32
+}
+
+// PolyU64 of the pair-shift hashes of the 32-word blocks; an empty message is one
+// empty block.
+pub fn preproc_poly(
+    prep: (&[u64; 65], &[u64; 65]),
+    a: FieldElem89,
+    b: FieldElem89,
+    c: FieldElem89,
+    l: usize,
+    xs: &[u64],
+) -> u64 {
+    let blocks = if xs.is_empty() {
+        vec![xs]
+    } else {
+        xs.chunks(32).collect()
+    };
+    let mut h = PolyU64::new(a, b, c);
+    for block in blocks {
+        let q1 = pair_shift(prep.0, 32, block);
+        let q2 = pair_shift(prep.1, 32, block);
+        h.write_u64(u64::from(q1) | u64::from(q2) << 32);
+    }
+    h.finish(Bits::new(l).unwrap())
+}
@@ -0,0 +1,124 @@
+use imp::{FieldElem61, OddU64, M61};
+use rng::SplitMix64;
+
+////////////////////////////////////////
+// Adversarial Inputs
+////////////////////////////////////////
+
+// Description: Inputs chosen against a family. With the key known, every hash
+// value has a preimage set that can be listed directly: multiply-shift is a
+// bijection on 64-bit words followed by a truncation, and multiply-mod-prime a
+// bijection mod p followed by one, so an adversary can fill a single bucket with
+// as many keys as they like. With the key unknown, the adversary can only choose
+// structure; arithmetic progressions with power-of-two strides are searched for
+// the most collisions on average over random keys.
+
+// Inverse of odd a mod 2^64 by Newton's iteration, which doubles the number of
+// correct low bits per step; a is its own inverse mod 8.
+pub fn inverse_u64(a: u64) -> u64 {
+    debug_assert!(a & 1 == 1);
+    let mut inv = a;
+    for _ in 0..5 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(a.wrapping_mul(inv)));
+    }
+    inv
+}
+
+// All x with shift_u64(l, a, x) = value, by the discarded low bits of a x.
+// Interface: 0 < l <= 64, value < 2^l
+pub fn shift_u64_preimages(l: usize, a: OddU64, value: u64) -> impl Iterator<Item = u64> {
+    let inv = inverse_u64(a.get());
+    let high = value << (64 - l);
+    (0..1u64 << (64 - l)).map(move |low| inv.wrapping_mul(high | low))
+}
+
+fn mul_m61(a: u64, b: u64) -> u64 {
+    let r = u128::from(a) * u128::from(b);
+    let s = ((r as u64) & M61) + ((r >> 61) as u64);
+    if s >= M61 {
+        s - M61
+    } else {
+        s
+    }
+}
+
+// Inverse of a mod p = 2^61 - 1 as a^(p - 2).
+pub fn inverse_m61(a: u64) -> u64 {
+    debug_assert!(a != 0 && a < M61);
+    let (mut base, mut exp, mut inv) = (a, M61 - 2, 1);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            inv = mul_m61(inv, base);
+        }
+        base = mul_m61(base, base);
+        exp >>= 1;
+    }
+    inv
+}
+
+// All x < p with mmp_p61_u60_128(l, a, b, x) = value, by (a x + b) mod p.
+// Interface: a != 0, l < 61, value < 2^l
+pub fn mmp_p61_preimages(
+    l: usize,
+    a: FieldElem61,
+    b: FieldElem61,
+    value: u64,
+) -> impl Iterator<Item = u64> {
+    let (a, b) = (a.get(), b.get());
+    let inv = inverse_m61(a);
+    (0u64..)
+        .map(move |k| value + (k << l))
+        .take_while(|&y| y < M61)
+        .map(move |y| {
+            let d = y + M61 - b;
+            let d = if d >= M61 { d - M61 } else { d };
+            mul_m61(d, inv)
+        })
+}
+
+// Pairs of equal values.
+pub fn colliding_pairs(mut values: Vec<u64>) -> u64 {
+    values.sort_unstable();
+    let mut pairs = 0;
+    let mut run = 0;
+    for i in 1..values.len() {
+        if values[i] == values[i - 1] {
+            run += 1;
+            pairs += run;
+        } else {
+            run = 0;
+        }
+    }
+    pairs
+}
+
+// Mean and most colliding pairs of the n keys i 2^s, i < n, over trials keys of
+// the family, for each stride 2^s that keeps the keys below 2^in_bits. Universality
+// bounds the mean, but not the few keys that put a progression into few buckets.
+// Interface: n a power of two
+pub fn stride_collisions<F, H>(
+    mut keygen: F,
+    in_bits: usize,
+    n: usize,
+    trials: usize,
+) -> Vec<(usize, f64, u64)>
+where
+    F: FnMut(&mut SplitMix64) -> H,
+    H: Fn(u64) -> u64,
+{
+    let mut rng = SplitMix64::new(0x8b3e61f0d4a2c957);
+    let n_bits = n.trailing_zeros() as usize;
+    (0..=in_bits - n_bits)
+        .map(|s| {
+            let (mut sum, mut max) = (0, 0);
+            for _ in 0..trials {
+                let hash = keygen(&mut rng);
+                let values = (0..n as u64).map(|i| hash(i << s)).collect();
+                let pairs = colliding_pairs(values);
+                sum += pairs;
+                max = max.max(pairs);
+            }
+            (s, sum as f64 / trials as f64, max)
+        })
+        .collect()
+}
@@ -0,0 +1,47 @@
+#[cfg(target_os = "linux")]
+use libc;
+#[cfg(target_os = "linux")]
+use std::mem;
+
+////////////////////////////////////////
+// Thread Affinity
+////////////////////////////////////////
+
+// Description: Pins the calling thread to one core (Linux only), so threads of a
+// multi-threaded experiment neither share a core nor migrate during a trial.
+// Cores are the ones the process may run on, which in a container or under
+// taskset can be fewer than the machine has.
+
+// Cores the process may run on, or none if unknown.
+#[cfg(target_os = "linux")]
+pub fn allowed_cores() -> Vec<usize> {
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        if libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return Vec::new();
+        }
+        (0..libc::CPU_SETSIZE as usize)
+            .filter(|&core| libc::CPU_ISSET(core, &set))
+            .collect()
+    }
+}
+
+// Whether the calling thread is now pinned to core.
+#[cfg(target_os = "linux")]
+pub fn pin_to_core(core: usize) -> bool {
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn allowed_cores() -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_to_core(_core: usize) -> bool {
+    false
+}
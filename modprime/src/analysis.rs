@@ -0,0 +1,127 @@
+////////////////////////////////////////
+// Statistical Analysis
+////////////////////////////////////////
+
+// Description: Statistics of a hash function with a fixed key over an input
+// distribution. The bucket chi-square measures how evenly the inputs spread over
+// the outputs. The avalanche statistics flip one input bit at a time: the strict
+// avalanche criterion (SAC) matrix holds, per input bit and output bit, how often
+// the output bit flips, ideally 1/2; the bit independence criterion (BIC) matrix
+// holds, per pair of output bits, the largest correlation between their flips
+// over all input bits, ideally 0.
+
+// Chi-square of the bucket counts against a uniform distribution; its expected
+// value is buckets - 1 if the values are uniform.
+// Interface: values < buckets
+pub fn chi_square(values: &[u64], buckets: usize) -> f64 {
+    let mut counts = vec![0u32; buckets];
+    for &value in values {
+        counts[value as usize] += 1;
+    }
+    let expected = values.len() as f64 / buckets as f64;
+    counts
+        .iter()
+        .map(|&count| (f64::from(count) - expected).powi(2) / expected)
+        .sum()
+}
+
+pub struct Avalanche {
+    // sac[i][j]: Pr[output bit j flips | input bit i flips]
+    pub sac: Vec<Vec<f64>>,
+    // bic[j][k]: max over input bits of |corr(flip of j, flip of k)|, j != k
+    pub bic: Vec<Vec<f64>>,
+}
+
+// Interface: hash maps in_bits-bit inputs to out_bits-bit outputs.
+pub fn avalanche<H>(hash: H, in_bits: usize, out_bits: usize, inputs: &[u64]) -> Avalanche
+where
+    H: Fn(u64) -> u64,
+{
+    let n = inputs.len() as f64;
+    let mut sac = vec![vec![0.0; out_bits]; in_bits];
+    let mut bic = vec![vec![0.0f64; out_bits]; out_bits];
+
+    let mut flips = vec![0u32; out_bits];
+    let mut both = vec![vec![0u32; out_bits]; out_bits];
+    for (i, sac) in sac.iter_mut().enumerate() {
+        for count in flips.iter_mut() {
+            *count = 0;
+        }
+        for row in both.iter_mut() {
+            for count in row.iter_mut() {
+                *count = 0;
+            }
+        }
+
+        for &x in inputs {
+            let d = hash(x) ^ hash(x ^ 1 << i);
+            for j in 0..out_bits {
+                if d >> j & 1 == 1 {
+                    flips[j] += 1;
+                    for (k, count) in both[j].iter_mut().enumerate().skip(j + 1) {
+                        *count += (d >> k & 1) as u32;
+                    }
+                }
+            }
+        }
+
+        for j in 0..out_bits {
+            sac[j] = f64::from(flips[j]) / n;
+        }
+        for j in 0..out_bits {
+            for k in j + 1..out_bits {
+                let (pj, pk) = (sac[j], sac[k]);
+                let var = pj * (1.0 - pj) * pk * (1.0 - pk);
+                // Constant flips carry no information about each other.
+                let corr = if var > 0.0 {
+                    (f64::from(both[j][k]) / n - pj * pk) / var.sqrt()
+                } else {
+                    0.0
+                };
+                bic[j][k] = bic[j][k].max(corr.abs());
+                bic[k][j] = bic[j][k];
+            }
+        }
+    }
+
+    Avalanche { sac, bic }
+}
+
+////////////////////////////////////////
+// Balls into Bins
+////////////////////////////////////////
+
+// Description: Expected loads of m buckets after n keys, if each key goes to a
+// bucket uniformly and independently. The load of a bucket is binomial with
+// variance n/m (1 - 1/m); the maximum load uses the Poisson approximation with
+// independent buckets, Pr[max <= k] = Pr[Pois(n/m) <= k]^m.
+
+pub fn expected_empty(n: usize, m: usize) -> f64 {
+    m as f64 * (n as f64 * (-1.0 / m as f64).ln_1p()).exp()
+}
+
+pub fn expected_load_variance(n: usize, m: usize) -> f64 {
+    let p = 1.0 / m as f64;
+    n as f64 * p * (1.0 - p)
+}
+
+pub fn expected_max_load(n: usize, m: usize) -> f64 {
+    let lambda = n as f64 / m as f64;
+    // E[max] = sum over k >= 0 of Pr[max > k], with tail(k) = Pr[Pois > k] summed
+    // upwards so that it stays accurate where it is tiny.
+    let pmf = |k: usize| (-lambda + k as f64 * lambda.ln() - ln_factorial(k)).exp();
+    let mut expected = 0.0;
+    for k in 0.. {
+        let tail = (k + 1..k + 200).map(pmf).sum::<f64>();
+        let exceeds = -(m as f64 * (-tail).ln_1p()).exp_m1();
+        if exceeds < 1e-12 {
+            break;
+        }
+        expected += exceeds;
+    }
+    expected
+}
+
+fn ln_factorial(k: usize) -> f64 {
+    (2..=k).map(|i| (i as f64).ln()).sum()
+}
@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+
+use analysis;
+use rng::SplitMix64;
+
+////////////////////////////////////////
+// Test Battery
+////////////////////////////////////////
+
+// Description: Key sets after SMHasher for 32-bit hashes of messages of 64-bit
+// words (bytes in big-endian order). Each key set is hashed with one fixed key and
+// passes if it has no more collisions than a random function would, up to four
+// standard deviations, and if the top bits of the hashes are uniform by
+// chi-square, up to four standard deviations. Key sets are split into groups, and
+// only keys of the same group count as colliding, so that groups may share keys.
+
+// Calls f with the group and the words of each key.
+pub type KeySet = (&'static str, fn(&mut dyn FnMut(usize, &[u64])));
+
+pub const KEY_SETS: [KeySet; 6] = [
+    ("sparse", sparse),
+    ("cyclic-3", cyclic_3),
+    ("cyclic-8", cyclic_8),
+    ("two-bytes", two_bytes),
+    ("permutation", permutation),
+    ("window", window),
+];
+
+// Bits of the hash for the bucket chi-square.
+const BUCKET_BITS: usize = 10;
+
+pub struct Outcome {
+    pub keys: usize,
+    pub collisions: u64,
+    pub expected: f64,
+    // Chi-square per degree of freedom, if the test has buckets.
+    pub chi2_df: Option<f64>,
+}
+
+impl Outcome {
+    pub fn pass(&self) -> bool {
+        let df = ((1 << BUCKET_BITS) - 1) as f64;
+        let colliding =
+            self.collisions as f64 > self.expected + 4.0 * self.expected.max(1.0).sqrt();
+        let skewed = self
+            .chi2_df
+            .is_some_and(|chi2_df| chi2_df * df > df + 4.0 * (2.0 * df).sqrt());
+        !colliding && !skewed
+    }
+}
+
+// Hashes of the keys as group << 32 | hash.
+pub fn evaluate(mut hashes: Vec<u64>) -> Outcome {
+    hashes.sort_unstable();
+
+    let mut collisions = 0;
+    let mut expected = 0.0;
+    let mut group_start = 0;
+    for i in 0..hashes.len() {
+        if i > 0 && hashes[i] == hashes[i - 1] {
+            collisions += 1;
+        }
+        if i + 1 == hashes.len() || hashes[i + 1] >> 32 != hashes[i] >> 32 {
+            expected += expected_collisions(i + 1 - group_start);
+            group_start = i + 1;
+        }
+    }
+
+    let buckets = hashes
+        .iter()
+        .map(|&hash| (hash as u32 >> (32 - BUCKET_BITS)) as u64)
+        .collect::<Vec<_>>();
+    let chi2 = analysis::chi_square(&buckets, 1 << BUCKET_BITS);
+
+    Outcome {
+        keys: hashes.len(),
+        collisions,
+        expected,
+        chi2_df: Some(chi2 / ((1 << BUCKET_BITS) - 1) as f64),
+    }
+}
+
+// Expected number of keys that hash to an already used value, for n keys and a
+// random function to 32 bits.
+pub fn expected_collisions(n: usize) -> f64 {
+    let m = (1u64 << 32) as f64;
+    let n = n as f64;
+    n + m * (n * (-1.0 / m).ln_1p()).exp_m1()
+}
+
+// Keys of 128 bits with at most 3 bits set.
+fn sparse(f: &mut dyn FnMut(usize, &[u64])) {
+    let key = |bits: &[usize]| {
+        let mut words = [0u64; 2];
+        for &bit in bits {
+            words[bit / 64] |= 1 << (bit % 64);
+        }
+        words
+    };
+    f(0, &key(&[]));
+    for i in 0..128 {
+        f(0, &key(&[i]));
+        for j in i + 1..128 {
+            f(0, &key(&[i, j]));
+            for k in j + 1..128 {
+                f(0, &key(&[i, j, k]));
+            }
+        }
+    }
+}
+
+// Random keys of 32 bytes that repeat a cycle of n bytes, all distinct.
+fn cyclic(n: usize, seed: u64, f: &mut dyn FnMut(usize, &[u64])) {
+    let mut rng = SplitMix64::new(seed);
+    let mut seen = HashSet::new();
+    while seen.len() < 1 << 17 {
+        let cycle = rng.next_u64() & !0 >> (64 - 8 * n);
+        if !seen.insert(cycle) {
+            continue;
+        }
+        let mut words = [0u64; 4];
+        for i in 0..32 {
+            let byte = cycle >> (8 * (i % n)) & 0xff;
+            words[i / 8] |= byte << (56 - 8 * (i % 8));
+        }
+        f(0, &words);
+    }
+}
+
+fn cyclic_3(f: &mut dyn FnMut(usize, &[u64])) {
+    cyclic(3, 0x5c1e8d2b7a094f36, f);
+}
+
+fn cyclic_8(f: &mut dyn FnMut(usize, &[u64])) {
+    cyclic(8, 0xe3a70b95d41c6f28, f);
+}
+
+// Keys of 16 bytes with at most two nonzero bytes.
+fn two_bytes(f: &mut dyn FnMut(usize, &[u64])) {
+    let key = |bytes: &[(usize, u64)]| {
+        let mut words = [0u64; 2];
+        for &(i, byte) in bytes {
+            words[i / 8] |= byte << (56 - 8 * (i % 8));
+        }
+        words
+    };
+    f(0, &key(&[]));
+    for i in 0..16 {
+        for x in 1..256 {
+            f(0, &key(&[(i, x)]));
+            for j in i + 1..16 {
+                for y in 1..256 {
+                    f(0, &key(&[(i, x), (j, y)]));
+                }
+            }
+        }
+    }
+}
+
+// Keys of six 32-bit blocks, each one of eight blocks with few bits set or clear.
+fn permutation(f: &mut dyn FnMut(usize, &[u64])) {
+    let blocks: [u64; 8] = [0, 1, 2, 3, 0x80000000, 0x40000000, 0xc0000000, 0xffffffff];
+    for i in 0..1 << 18 {
+        let block = |j: usize| blocks[i >> (3 * j) & 7];
+        let words = [
+            block(0) << 32 | block(1),
+            block(2) << 32 | block(3),
+            block(4) << 32 | block(5),
+        ];
+        f(0, &words);
+    }
+}
+
+// Keys of 128 bits with all values of a 16-bit window and zeros elsewhere; one
+// group per window position, in steps of 8 bits.
+fn window(f: &mut dyn FnMut(usize, &[u64])) {
+    for (group, shift) in (0..=112).step_by(8).enumerate() {
+        for x in 0..1u128 << 16 {
+            let key = x << shift;
+            f(group, &[(key >> 64) as u64, key as u64]);
+        }
+    }
+}
+
+// Random keys of 128 bits against the same key with one or two bits flipped;
+// returns the number of pairs and of colliding pairs.
+pub fn differential<H>(hash: &H) -> (usize, u64)
+where
+    H: Fn(&[u64]) -> u32,
+{
+    let mut rng = SplitMix64::new(0x4d9b2e07c58a16f3);
+    let mut pairs = 0;
+    let mut collisions = 0;
+    for _ in 0..256 {
+        let x = rng.next_u128();
+        let h = hash(&[(x >> 64) as u64, x as u64]);
+        for i in 0..128 {
+            for j in i..128 {
+                let y = x ^ 1 << i ^ if j > i { 1 << j } else { 0 };
+                if hash(&[(y >> 64) as u64, y as u64]) == h {
+                    collisions += 1;
+                }
+                pairs += 1;
+            }
+        }
+    }
+    (pairs, collisions)
+}
+
+// Hashes of one 128-bit key with many keys of the family.
+pub fn seeds<F, H>(keygen: &mut F) -> Vec<u64>
+where
+    F: FnMut(&mut SplitMix64) -> H,
+    H: Fn(&[u64]) -> u32,
+{
+    let mut rng = SplitMix64::new(0xa81f6c3d2e95b704);
+    let message = [rng.next_u64(), rng.next_u64()];
+    (0..1 << 17)
+        .map(|_| {
+            let hash = keygen(&mut SplitMix64::new(rng.next_u64()));
+            u64::from(hash(&message))
+        })
+        .collect()
+}
@@ -0,0 +1,146 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io;
+
+use table::{invalid, Table};
+use Stats;
+
+////////////////////////////////////////
+// Baseline Comparison
+////////////////////////////////////////
+
+// Description: Compares benchmark cases against a previous CSV result file of
+// experiment 1 or 2, such as output/experiment1.csv. Rows are matched by case name,
+// "scheme/bits" or family as in the registry. A case regresses if its median is
+// slower than the baseline by more than the threshold and, when both runs have
+// confidence intervals, the intervals do not overlap; improvements likewise.
+// Interface: the threshold is a relative change, e.g. 0.05 for 5%.
+
+#[derive(Clone, Copy, Debug)]
+struct Row {
+    median: f64,
+    ci95: Option<(f64, f64)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Baseline {
+    path: String,
+    column: String,
+    rows: HashMap<String, Row>,
+    threshold: f64,
+    compared: Cell<usize>,
+    regressions: Cell<usize>,
+    improvements: Cell<usize>,
+    missing: Cell<usize>,
+}
+
+impl Baseline {
+    pub fn load(path: &str, threshold: f64) -> io::Result<Self> {
+        let table = Table::read(path)?;
+        let find = |name: &str| table.column(name);
+
+        let column = ["nanos", "cycles"]
+            .iter()
+            .find(|&&name| find(name).is_some())
+            .ok_or_else(|| invalid("no nanos or cycles column"))?;
+        let value = find(column).unwrap();
+        let ci = find("ci_low").zip(find("ci_high"));
+        let key = match (find("scheme"), find("bits"), find("family")) {
+            (Some(scheme), Some(bits), _) => (scheme, Some(bits)),
+            (_, _, Some(family)) => (family, None),
+            _ => return Err(invalid("no scheme and bits or family columns")),
+        };
+
+        let mut rows = HashMap::new();
+        for fields in &table.rows {
+            let name = match key {
+                (scheme, Some(bits)) => format!("{}/{}", fields[scheme], fields[bits]),
+                (family, None) => fields[family].clone(),
+            };
+            let ci95 = match ci {
+                Some((low, high)) => {
+                    Some((Table::number(fields, low)?, Table::number(fields, high)?))
+                }
+                None => None,
+            };
+            let row = Row {
+                median: Table::number(fields, value)?,
+                ci95,
+            };
+            rows.insert(name, row);
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            column: column.to_string(),
+            rows,
+            threshold,
+            compared: Cell::new(0),
+            regressions: Cell::new(0),
+            improvements: Cell::new(0),
+            missing: Cell::new(0),
+        })
+    }
+
+    // Name of the CSV column the baseline was timed in, "nanos" or "cycles".
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    // Prints how the case changed against the baseline and records the verdict.
+    pub fn check(&self, name: &str, unit: &str, stats: &Stats) {
+        let row = match self.rows.get(name) {
+            Some(row) => row,
+            None => {
+                self.missing.set(self.missing.get() + 1);
+                println!(
+                    "Case: {}; current: {:.4} {}; not in baseline",
+                    name, stats.median, unit
+                );
+                return;
+            }
+        };
+        self.compared.set(self.compared.get() + 1);
+
+        let change = stats.median / row.median - 1.0;
+        let (slower, faster) = match row.ci95 {
+            Some((low, high)) => (stats.ci95.0 > high, stats.ci95.1 < low),
+            None => (true, true),
+        };
+        let verdict = if change > self.threshold && slower {
+            self.regressions.set(self.regressions.get() + 1);
+            "regression"
+        } else if change < -self.threshold && faster {
+            self.improvements.set(self.improvements.get() + 1);
+            "improvement"
+        } else {
+            "unchanged"
+        };
+
+        println!(
+            "Case: {}; baseline: {:.4} {}, current: {:.4} {}, change: {:+.2}%; {}",
+            name,
+            row.median,
+            unit,
+            stats.median,
+            unit,
+            change * 100.0,
+            verdict
+        );
+    }
+
+    // Prints a summary; true if no case regressed.
+    pub fn finish(&self) -> bool {
+        println!(
+            "Compared {} cases against {} (threshold {}%): {} regressions, {} improvements, \
+             {} not in baseline",
+            self.compared.get(),
+            self.path,
+            self.threshold * 100.0,
+            self.regressions.get(),
+            self.improvements.get(),
+            self.missing.get()
+        );
+        self.regressions.get() == 0
+    }
+}
@@ -0,0 +1,223 @@
+use byteorder::{BigEndian, ByteOrder};
+
+use imp::{self, Bits, FieldElem89};
+
+////////////////////////////////////////
+// Feature Hashing
+////////////////////////////////////////
+
+// Description: The hashing trick. Tokens are hashed straight to one of 2^l
+// coordinates of a sparse vector; the signed variant multiplies each count by +-1
+// from an independent sign hash, so collisions cancel in expectation.
+// Interface: index(token, l) < 2^l; sign(token, 1) is 0 or 1.
+// Parameters: l <= 32
+
+pub struct FeatureHasher<H, S> {
+    l: usize,
+    index: H,
+    sign: Option<S>,
+}
+
+impl<H> FeatureHasher<H, H>
+where
+    H: Fn(&[u8], usize) -> u64,
+{
+    #[inline]
+    pub fn new(l: usize, index: H) -> Self {
+        assert!(l <= 32);
+        Self {
+            l,
+            index,
+            sign: None,
+        }
+    }
+}
+
+impl<H, S> FeatureHasher<H, S>
+where
+    H: Fn(&[u8], usize) -> u64,
+    S: Fn(&[u8], usize) -> u64,
+{
+    #[inline]
+    pub fn signed(l: usize, index: H, sign: S) -> Self {
+        assert!(l <= 32);
+        Self {
+            l,
+            index,
+            sign: Some(sign),
+        }
+    }
+
+    #[inline]
+    pub fn dimension(&self) -> usize {
+        1 << self.l
+    }
+
+    // Sparse vector as (coordinate, value) pairs sorted by coordinate, without zeros.
+    pub fn transform<T>(&self, tokens: &[T]) -> Vec<(u32, f64)>
+    where
+        T: AsRef<[u8]>,
+    {
+        let mut entries = tokens
+            .iter()
+            .map(|token| {
+                let token = token.as_ref();
+                let i = (self.index)(token, self.l);
+                debug_assert!(i < 1 << self.l);
+                let value = match self.sign {
+                    Some(ref sign) if sign(token, 1) == 0 => -1.0,
+                    _ => 1.0,
+                };
+                (i as u32, value)
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|&(i, _)| i);
+
+        let mut vector: Vec<(u32, f64)> = Vec::with_capacity(entries.len());
+        for (i, value) in entries {
+            match vector.last_mut() {
+                Some(last) if last.0 == i => last.1 += value,
+                _ => vector.push((i, value)),
+            }
+        }
+        vector.retain(|&(_, value)| value != 0.0);
+        vector
+    }
+
+    #[inline]
+    pub fn transform_text(&self, text: &[u8]) -> Vec<(u32, f64)> {
+        self.transform(&tokenize(text))
+    }
+}
+
+////////////////////////////////////////
+// Tokens
+////////////////////////////////////////
+
+// Lower-cased runs of letters and digits. Bytes >= 0x80 count as letters, which
+// keeps ISO 8859-1 words such as those in input.txt in one piece.
+pub fn tokenize(text: &[u8]) -> Vec<Vec<u8>> {
+    text.split(|&b| b < 0x80 && !b.is_ascii_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_ascii_lowercase())
+        .collect()
+}
+
+// Constants: p = 2^89 - 1
+// Description: PolyU64 over the zero-padded big-endian 8-byte chunks of the token,
+// followed by its length.
+// Interface: m = 2^l, l <= 64
+// Parameters: a, b, c < p
+
+#[inline]
+pub fn poly_bytes(
+    l: Bits<64>,
+    a: FieldElem89,
+    b: FieldElem89,
+    c: FieldElem89,
+    token: &[u8],
+) -> u64 {
+    let mut h = imp::PolyU64::new(a, b, c);
+    for chunk in token.chunks(8) {
+        let mut buf = [0; 8];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        h.write_u64(BigEndian::read_u64(&buf));
+    }
+    h.write_u64(token.len() as u64);
+    h.finish(l)
+}
+
+// Description: VectorShiftU32D64 over the zero-padded big-endian 4-byte chunks of
+// the token, followed by its length. Only the first 252 bytes are hashed.
+// Interface: m = 2^l, l <= 32
+// Parameters: a[i] < 2^64
+
+#[inline]
+pub fn vector_shift_bytes(l: Bits<32>, a: [u64; 65], token: &[u8]) -> u32 {
+    let mut h = imp::VectorShiftU32D64::new(a);
+    for chunk in token.chunks(4).take(63) {
+        let mut buf = [0; 4];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        h.write_u32(BigEndian::read_u32(&buf));
+    }
+    h.write_u32(token.len() as u32);
+    h.finish(l)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rng::SplitMix64;
+
+    #[test]
+    fn tokens() {
+        let tokens = tokenize(b"Das ist -- 42 F\xfcnf, 3x so gro\xdf!");
+        let expected: Vec<&[u8]> =
+            vec![b"das", b"ist", b"42", b"f\xfcnf", b"3x", b"so", b"gro\xdf"];
+        assert_eq!(tokens, expected);
+        assert!(tokenize(b" ,.- ").is_empty());
+    }
+
+    #[test]
+    fn transform_counts_tokens() {
+        let mut rng = SplitMix64::new(0x72be5d74f27b896f);
+        let (a, b, c) = (rng.below_m89(), rng.below_m89(), rng.below_m89());
+        let l = Bits::new(4).unwrap();
+        let index = move |token: &[u8], _: usize| poly_bytes(l, a, b, c, token);
+        let hasher = FeatureHasher::new(4, index);
+        assert_eq!(hasher.dimension(), 16);
+
+        let text = b"the quick brown fox jumps over the lazy dog the end";
+        let vector = hasher.transform_text(text);
+        assert!(vector.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(vector.iter().all(|&(i, value)| i < 16 && value > 0.0));
+        let total = vector.iter().map(|&(_, value)| value).sum::<f64>();
+        assert_eq!(total, tokenize(text).len() as f64);
+
+        // A sign hash that is always negative; one that cancels a pair to zero.
+        let negative = FeatureHasher::signed(4, index, |_: &[u8], _| 0);
+        let negated = vector
+            .iter()
+            .map(|&(i, value)| (i, -value))
+            .collect::<Vec<_>>();
+        assert_eq!(negative.transform_text(text), negated);
+        let cancel =
+            FeatureHasher::signed(0, |_: &[u8], _| 0, |token: &[u8], _| token[0] as u64 & 1);
+        assert_eq!(cancel.transform(&[b"a", b"b"]), vec![]);
+        assert_eq!(cancel.transform(&[b"a", b"c"]), vec![(0, 2.0)]);
+    }
+
+    #[test]
+    fn byte_hashes_include_length() {
+        let mut rng = SplitMix64::new(0x9bdc06a725c71235);
+        let (a, b, c) = (rng.below_m89(), rng.below_m89(), rng.below_m89());
+        let mut keys = [0; 65];
+        rng.fill_u64(&mut keys);
+
+        // Zero padding alone would make these collide.
+        let tokens: [&[u8]; 4] = [b"", b"\0", b"ab", b"ab\0\0"];
+        for (i, x) in tokens.iter().enumerate() {
+            for y in &tokens[i + 1..] {
+                assert_ne!(
+                    poly_bytes(Bits::MAX, a, b, c, x),
+                    poly_bytes(Bits::MAX, a, b, c, y)
+                );
+                assert_ne!(
+                    vector_shift_bytes(Bits::MAX, keys, x),
+                    vector_shift_bytes(Bits::MAX, keys, y)
+                );
+            }
+        }
+
+        // Only the first 252 bytes count, so long tokens fit in d = 64 words.
+        let long = vec![7; 1000];
+        let mut other = long.clone();
+        other[999] = 8;
+        assert_eq!(
+            vector_shift_bytes(Bits::MAX, keys, &long),
+            vector_shift_bytes(Bits::MAX, keys, &other)
+        );
+        assert!(vector_shift_bytes(Bits::new(5).unwrap(), keys, &long) < 32);
+    }
+}
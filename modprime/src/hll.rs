@@ -0,0 +1,444 @@
+use byteorder::{BigEndian, ByteOrder};
+
+////////////////////////////////////////
+// HyperLogLog
+////////////////////////////////////////
+
+// Description: HyperLogLog cardinality estimator over 64-bit hash values. Small
+// sketches are kept sparse at precision 25 and switch to 2^p dense registers once
+// the sparse list would use more memory. Estimates use Ertl's improved estimator,
+// which corrects the small- and large-range bias of the raw estimate without
+// empirical tables.
+// Interface: hashes are 64-bit and top-aligned (index taken from the high bits).
+// Parameters: 4 <= p <= 18
+
+pub const MIN_PRECISION: usize = 4;
+pub const MAX_PRECISION: usize = 18;
+
+const SPARSE_P: usize = 25;
+const SPARSE_Q: usize = 64 - SPARSE_P;
+
+const TAG_SPARSE: u8 = 0;
+const TAG_DENSE: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Repr {
+    // Sorted by index; entries are index << 6 | rho.
+    Sparse(Vec<u32>),
+    Dense(Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HyperLogLog {
+    p: usize,
+    repr: Repr,
+}
+
+impl HyperLogLog {
+    pub fn new(p: usize) -> Self {
+        assert!((MIN_PRECISION..=MAX_PRECISION).contains(&p));
+        let repr = Repr::Sparse(Vec::new());
+        Self { p, repr }
+    }
+
+    #[inline]
+    pub fn precision(&self) -> usize {
+        self.p
+    }
+
+    #[inline]
+    pub fn is_sparse(&self) -> bool {
+        match self.repr {
+            Repr::Sparse(_) => true,
+            Repr::Dense(_) => false,
+        }
+    }
+
+    #[inline]
+    pub fn insert(&mut self, hash: u64) {
+        let p = self.p;
+        let converted = match self.repr {
+            Repr::Sparse(ref mut sparse) => {
+                let idx = (hash >> (64 - SPARSE_P)) as u32;
+                let rho = rho(hash << SPARSE_P, SPARSE_Q);
+                insert_sparse(sparse, idx << 6 | rho as u32);
+                sparse.len() > (1 << p) / 4
+            }
+            Repr::Dense(ref mut dense) => {
+                let idx = (hash >> (64 - p)) as usize;
+                let rho = rho(hash << p, 64 - p);
+                if rho > dense[idx] {
+                    dense[idx] = rho;
+                }
+                false
+            }
+        };
+        if converted {
+            self.densify();
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(
+            self.p, other.p,
+            "cannot merge sketches of different precision"
+        );
+        let p = self.p;
+        let converted = match (&mut self.repr, &other.repr) {
+            (Repr::Sparse(sparse), Repr::Sparse(entries)) => {
+                for &entry in entries {
+                    insert_sparse(sparse, entry);
+                }
+                sparse.len() > (1 << p) / 4
+            }
+            _ => true,
+        };
+        if !converted {
+            return;
+        }
+        self.densify();
+        if let Repr::Dense(ref mut dense) = self.repr {
+            match other.repr {
+                Repr::Sparse(ref entries) => {
+                    for &entry in entries {
+                        let (idx, rho) = sparse_to_dense(p, entry);
+                        if rho > dense[idx] {
+                            dense[idx] = rho;
+                        }
+                    }
+                }
+                Repr::Dense(ref registers) => {
+                    for (r, &s) in dense.iter_mut().zip(registers) {
+                        if s > *r {
+                            *r = s;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn estimate(&self) -> f64 {
+        match self.repr {
+            Repr::Sparse(ref sparse) => {
+                let mut hist = vec![0; SPARSE_Q + 2];
+                hist[0] = (1 << SPARSE_P) - sparse.len();
+                for &entry in sparse {
+                    hist[(entry & 63) as usize] += 1;
+                }
+                estimate_ertl(SPARSE_Q, &hist)
+            }
+            Repr::Dense(ref dense) => {
+                let q = 64 - self.p;
+                let mut hist = vec![0; q + 2];
+                for &r in dense {
+                    hist[r as usize] += 1;
+                }
+                estimate_ertl(q, &hist)
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.p as u8];
+        match self.repr {
+            Repr::Sparse(ref sparse) => {
+                out.push(TAG_SPARSE);
+                let mut buf = vec![0; 4 * (sparse.len() + 1)];
+                BigEndian::write_u32(&mut buf[..4], sparse.len() as u32);
+                BigEndian::write_u32_into(sparse, &mut buf[4..]);
+                out.extend_from_slice(&buf);
+            }
+            Repr::Dense(ref dense) => {
+                out.push(TAG_DENSE);
+                out.extend_from_slice(dense);
+            }
+        }
+        out
+    }
+
+    // Returns None if the bytes are not a sketch written by to_bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 2 {
+            return None;
+        }
+        let p = bytes[0] as usize;
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&p) {
+            return None;
+        }
+        let body = &bytes[2..];
+        let repr = match bytes[1] {
+            TAG_SPARSE => {
+                if body.len() < 4 {
+                    return None;
+                }
+                let len = BigEndian::read_u32(&body[..4]) as usize;
+                if len > (1 << p) / 4 || body.len() != 4 * (len + 1) {
+                    return None;
+                }
+                let mut sparse = vec![0; len];
+                BigEndian::read_u32_into(&body[4..], &mut sparse);
+                let sorted = sparse.windows(2).all(|w| w[0] >> 6 < w[1] >> 6);
+                let valid = sparse.iter().all(|&e| {
+                    let rho = (e & 63) as usize;
+                    e >> 6 < (1 << SPARSE_P) && (1..=SPARSE_Q + 1).contains(&rho)
+                });
+                if !sorted || !valid {
+                    return None;
+                }
+                Repr::Sparse(sparse)
+            }
+            TAG_DENSE => {
+                if body.len() != 1 << p || body.iter().any(|&r| r as usize > 64 - p + 1) {
+                    return None;
+                }
+                Repr::Dense(body.to_vec())
+            }
+            _ => return None,
+        };
+        Some(Self { p, repr })
+    }
+
+    fn densify(&mut self) {
+        let p = self.p;
+        let dense = match self.repr {
+            Repr::Sparse(ref sparse) => {
+                let mut dense = vec![0; 1 << p];
+                for &entry in sparse {
+                    let (idx, rho) = sparse_to_dense(p, entry);
+                    if rho > dense[idx] {
+                        dense[idx] = rho;
+                    }
+                }
+                dense
+            }
+            Repr::Dense(_) => return,
+        };
+        self.repr = Repr::Dense(dense);
+    }
+}
+
+////////////////////////////////////////
+// Helper Functions
+////////////////////////////////////////
+
+// Position of the leftmost one-bit in the q high bits of w, or q + 1 if none.
+#[inline]
+fn rho(w: u64, q: usize) -> u8 {
+    let z = w.leading_zeros() as usize + 1;
+    if z > q + 1 {
+        (q + 1) as u8
+    } else {
+        z as u8
+    }
+}
+
+#[inline]
+fn insert_sparse(sparse: &mut Vec<u32>, entry: u32) {
+    match sparse.binary_search_by_key(&(entry >> 6), |&e| e >> 6) {
+        Ok(i) => {
+            if entry & 63 > sparse[i] & 63 {
+                sparse[i] = entry;
+            }
+        }
+        Err(i) => sparse.insert(i, entry),
+    }
+}
+
+// Recovers the dense register update that the original hash would have made.
+#[inline]
+fn sparse_to_dense(p: usize, entry: u32) -> (usize, u8) {
+    let idx = entry >> 6;
+    let rho = (entry & 63) as u8;
+    let extra = SPARSE_P - p;
+    let low = idx & ((1 << extra) - 1);
+    let rho = if low != 0 {
+        (low.leading_zeros() as usize - (32 - extra) + 1) as u8
+    } else {
+        extra as u8 + rho
+    };
+    ((idx >> extra) as usize, rho)
+}
+
+// Ertl, "New cardinality estimation algorithms for HyperLogLog sketches" (2017).
+// hist[k] counts registers with value k, for k in 0..=q+1.
+fn estimate_ertl(q: usize, hist: &[usize]) -> f64 {
+    let m = hist.iter().sum::<usize>() as f64;
+    let mut z = m * tau((m - hist[q + 1] as f64) / m);
+    for k in (1..=q).rev() {
+        z = 0.5 * (z + hist[k] as f64);
+    }
+    z += m * sigma(hist[0] as f64 / m);
+    let alpha = 0.5 / 2f64.ln();
+    alpha * m * m / z
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let z_old = z;
+        z += x * y;
+        y += y;
+        if z == z_old {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let z_old = z;
+        y *= 0.5;
+        z -= (1.0 - x) * (1.0 - x) * y;
+        if z == z_old {
+            return z / 3.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rng::SplitMix64;
+
+    fn sketch(p: usize, n: usize, seed: u64) -> HyperLogLog {
+        let mut rng = SplitMix64::new(seed);
+        let mut hll = HyperLogLog::new(p);
+        for _ in 0..n {
+            hll.insert(rng.next_u64());
+        }
+        hll
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        for p in MIN_PRECISION..=MAX_PRECISION {
+            for &n in &[0, 1, 10, 1 << p] {
+                let hll = sketch(p, n, 0x3c6ef372fe94f82b ^ n as u64);
+                assert_eq!(HyperLogLog::from_bytes(&hll.to_bytes()), Some(hll));
+            }
+        }
+    }
+
+    #[test]
+    fn bytes_rejected() {
+        let sparse = sketch(10, 100, 0xa54ff53a5f1d36f1).to_bytes();
+        let dense = sketch(10, 10_000, 0x510e527fade682d1).to_bytes();
+        assert!(HyperLogLog::new(10).is_sparse());
+        assert_eq!(sparse[1], TAG_SPARSE);
+        assert_eq!(dense[1], TAG_DENSE);
+
+        for bytes in &[&sparse, &dense] {
+            assert_eq!(HyperLogLog::from_bytes(&bytes[..1]), None);
+            assert_eq!(HyperLogLog::from_bytes(&bytes[..bytes.len() - 1]), None);
+
+            let mut precision = bytes.to_vec();
+            precision[0] = MAX_PRECISION as u8 + 1;
+            assert_eq!(HyperLogLog::from_bytes(&precision), None);
+
+            let mut tag = bytes.to_vec();
+            tag[1] = 2;
+            assert_eq!(HyperLogLog::from_bytes(&tag), None);
+        }
+
+        // Entries out of order, and a register beyond 64 - p + 1.
+        let mut unsorted = sparse.clone();
+        let (first, second) = unsorted[6..14].split_at_mut(4);
+        first.swap_with_slice(second);
+        assert_eq!(HyperLogLog::from_bytes(&unsorted), None);
+        let mut register = dense.clone();
+        register[2] = 64 - 10 + 2;
+        assert_eq!(HyperLogLog::from_bytes(&register), None);
+    }
+
+    #[test]
+    fn sparse_matches_dense() {
+        // Densifying a sparse sketch gives the registers that dense inserts would.
+        for p in MIN_PRECISION..=MAX_PRECISION {
+            let mut rng = SplitMix64::new(0x9b05688c2b3e6c1f ^ p as u64);
+            let hashes = (0..(1 << p) / 4)
+                .map(|i| match i % 4 {
+                    // Also hashes whose sparse index has no one-bit past the p high bits.
+                    0 => rng.next_u64() & !(!0 >> p ^ !0 >> SPARSE_P),
+                    _ => rng.next_u64(),
+                })
+                .collect::<Vec<_>>();
+
+            let mut sparse = HyperLogLog::new(p);
+            let mut dense = HyperLogLog {
+                p,
+                repr: Repr::Dense(vec![0; 1 << p]),
+            };
+            for &hash in &hashes {
+                sparse.insert(hash);
+                dense.insert(hash);
+            }
+            assert!(sparse.is_sparse());
+
+            let mut merged = HyperLogLog {
+                p,
+                repr: Repr::Dense(vec![0; 1 << p]),
+            };
+            merged.merge(&sparse);
+            sparse.densify();
+            assert_eq!(sparse, dense);
+            assert_eq!(merged, dense);
+        }
+    }
+
+    #[test]
+    fn merge_is_union() {
+        let p = 12;
+        let mut rng = SplitMix64::new(0x1f83d9abfb41bd6b);
+        let hashes = (0..20_000).map(|_| rng.next_u64()).collect::<Vec<_>>();
+
+        // Sparse with sparse, sparse with dense and dense with dense.
+        for &(a, b) in &[(100, 200), (100, 20_000), (10_000, 20_000)] {
+            let mut left = HyperLogLog::new(p);
+            let mut right = HyperLogLog::new(p);
+            for &hash in &hashes[..a] {
+                left.insert(hash);
+            }
+            for &hash in &hashes[a..b] {
+                right.insert(hash);
+            }
+            left.merge(&right);
+            let mut expected = HyperLogLog::new(p);
+            for &hash in &hashes[..b] {
+                expected.insert(hash);
+            }
+            assert_eq!(left.estimate(), expected.estimate());
+        }
+    }
+
+    #[test]
+    fn estimate_within_error() {
+        // Within four standard errors 1.04 / sqrt(2^p), in the sparse and dense ranges.
+        for &p in &[8, 12, 16] {
+            let tolerance = 4.0 * 1.04 / ((1 << p) as f64).sqrt();
+            for &n in &[0, 1, 100, 10_000, 1_000_000] {
+                let estimate = sketch(p, n, 0x5be0cd19137e2179 ^ n as u64).estimate();
+                let n = n as f64;
+                assert!(
+                    (estimate - n).abs() <= tolerance * n,
+                    "p = {}, n = {}: {}",
+                    p,
+                    n,
+                    estimate
+                );
+            }
+        }
+    }
+}
@@ -1,6 +1,113 @@
 #[cfg(test)]
 use test;
 
+////////////////////////////////////////
+// Parameters
+////////////////////////////////////////
+
+// Description: Keys and output lengths that meet the interface of the functions
+// below. The constructors check it in release builds too, so an invalid key is
+// rejected where it is made instead of hashing wrongly, and passing an output
+// length of one family to a narrower one does not compile.
+
+// Output length l, 0 < l <= L.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bits<const L: usize>(usize);
+
+impl<const L: usize> Bits<L> {
+    pub const MAX: Self = Bits(L);
+
+    #[inline]
+    pub const fn new(l: usize) -> Option<Self> {
+        if l > 0 && l <= L {
+            Some(Bits(l))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub const fn get(self) -> usize {
+        self.0
+    }
+}
+
+// Odd multipliers of multiply-shift.
+macro_rules! odd {
+    ($name:ident, $t:ty) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct $name($t);
+
+        impl $name {
+            #[inline]
+            pub const fn new(a: $t) -> Option<Self> {
+                if a & 1 == 1 {
+                    Some($name(a))
+                } else {
+                    None
+                }
+            }
+
+            #[inline]
+            pub const fn get(self) -> $t {
+                self.0
+            }
+        }
+    };
+}
+
+odd!(OddU32, u32);
+odd!(OddU64, u64);
+odd!(OddU128, u128);
+
+// Elements of the field mod p = 2^31 - 1, 2^61 - 1 or 2^89 - 1, the last as
+// little-endian 32-bit limbs.
+macro_rules! field_elem {
+    ($name:ident, $t:ty, |$x:ident| $below_p:expr) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct $name($t);
+
+        impl $name {
+            #[inline]
+            pub fn new($x: $t) -> Option<Self> {
+                if $below_p {
+                    Some($name($x))
+                } else {
+                    None
+                }
+            }
+
+            #[inline]
+            pub const fn get(self) -> $t {
+                self.0
+            }
+        }
+    };
+}
+
+field_elem!(FieldElem31, u32, |x| x < M31);
+field_elem!(FieldElem61, u64, |x| x < M61);
+field_elem!(FieldElem89, [u32; 3], |x| below_m89(x));
+
+impl FieldElem89 {
+    // x mod p, for keys drawn as any 96 bits.
+    #[inline]
+    pub fn reduce(x: [u32; 3]) -> Self {
+        // x = hi 2^89 + lo with hi < 2^7, and 2^89 = 1 mod p, so hi + lo < 2p.
+        let hi = x[2] >> 25;
+        let s0 = u64::from(x[0]) + u64::from(hi);
+        let s1 = u64::from(x[1]) + (s0 >> 32);
+        let s2 = (x[2] & M89[2]) + (s1 >> 32) as u32;
+        FieldElem89(trysubp3([s0 as u32, s1 as u32, s2]))
+    }
+}
+
+// Limbwise comparison from the most significant limb.
+#[inline]
+fn below_m89(x: [u32; 3]) -> bool {
+    x.iter().rev().lt(M89.iter().rev())
+}
+
 ////////////////////////////////////////
 // Multiply-Mod-Prime
 ////////////////////////////////////////
@@ -14,29 +121,27 @@ pub const M89: [u32; 3] = [0xffffffff, 0xffffffff, 0x01ffffff];
 // Parameters: a, b < p
 
 #[inline]
-pub fn mmp_p89_u64(l: usize, a: [u32; 3], b: [u32; 3], x: u64) -> u64 {
-    debug_assert!(l <= 64);
+pub fn mmp_p89_u64(l: Bits<64>, a: FieldElem89, b: FieldElem89, x: u64) -> u64 {
+    let l = l.get();
 
     let x = [x as u32, (x >> 32) as u32];
-    let [c0, c1, c2, c3, c4] = mul3x2(a, x);
-    let d = add6x3modp([c0, c1, c2, c3, c4, 0], b);
+    let [c0, c1, c2, c3, c4] = mul3x2(a.get(), x);
+    let d = add6x3modp([c0, c1, c2, c3, c4, 0], b.get());
     let q = (d[0] as u64) | (d[1] as u64) << 32;
 
-    q & ((1 << l) - 1)
+    q & (!0 >> (64 - l))
 }
 
 // Constants: p = 2^31 - 1
-// Interface: u <= p, m = 2^l, l <= 32
+// Interface: u <= p, m = 2^l, l <= 30
 // Parameters: a, b < p
 
 #[inline]
-pub fn mmp_p31_u30(l: usize, a: u32, b: u32, x: u32) -> u32 {
-    debug_assert!(l <= 30);
-    debug_assert!(a < M31);
-    debug_assert!(b < M31);
+pub fn mmp_p31_u30(l: Bits<30>, a: FieldElem31, b: FieldElem31, x: u32) -> u32 {
+    let l = l.get();
     debug_assert!(x < M31);
 
-    let r = u64::from(a) * u64::from(x) + u64::from(b);
+    let r = u64::from(a.get()) * u64::from(x) + u64::from(b.get());
     let s = ((r as u32) & M31) + ((r >> 31) as u32);
     let q = if s >= M31 { s - M31 } else { s };
 
@@ -45,11 +150,11 @@ pub fn mmp_p31_u30(l: usize, a: u32, b: u32, x: u32) -> u32 {
 
 // Description: Three independent mmp31 xor'd together.
 // Constants: p = 2^31 - 1
-// Interface: u = 2^64, m = 2^l, l < 31
+// Interface: u = 2^64, m = 2^l, l <= 30
 // Parameters: a0, a1, a2, b0, b1, b2 < p
 
 #[inline]
-pub fn mmp_p31_u64(l: usize, a: [u32; 3], b: [u32; 3], x: u64) -> u32 {
+pub fn mmp_p31_u64(l: Bits<30>, a: [FieldElem31; 3], b: [FieldElem31; 3], x: u64) -> u32 {
     let x0 = (x & 0x3fffffff) as u32;
     let x1 = ((x >> 30) & 0x3fffffff) as u32;
     let x2 = ((x >> 60) & 0x3fffffff) as u32;
@@ -62,17 +167,15 @@ pub fn mmp_p31_u64(l: usize, a: [u32; 3], b: [u32; 3], x: u64) -> u32 {
 }
 
 // Constants: p = 2^61 - 1
-// Interface: u <= p, m = 2^l, l < 61
+// Interface: u <= p, m = 2^l, l <= 60
 // Parameters: a, b < p
 
 #[inline]
-pub fn mmp_p61_u60_128(l: usize, a: u64, b: u64, x: u64) -> u64 {
-    debug_assert!(l <= 60);
-    debug_assert!(a < M61);
-    debug_assert!(b < M61);
+pub fn mmp_p61_u60_128(l: Bits<60>, a: FieldElem61, b: FieldElem61, x: u64) -> u64 {
+    let l = l.get();
     debug_assert!(x < M61);
 
-    let r = u128::from(a) * u128::from(x) + u128::from(b);
+    let r = u128::from(a.get()) * u128::from(x) + u128::from(b.get());
     let s = ((r as u64) & M61) + ((r >> 61) as u64);
     let q = if s >= M61 { s - M61 } else { s };
 
@@ -84,33 +187,28 @@ pub fn mmp_p61_u60_128(l: usize, a: u64, b: u64, x: u64) -> u64 {
 ////////////////////////////////////////
 
 #[inline]
-pub fn shift_u32(l: usize, a: u32, x: u32) -> u32 {
-    debug_assert!(l <= 32);
-    a.wrapping_mul(x) >> (32 - l)
+pub fn shift_u32(l: Bits<32>, a: OddU32, x: u32) -> u32 {
+    a.get().wrapping_mul(x) >> (32 - l.get())
 }
 
 #[inline]
-pub fn shift_u64(l: usize, a: u64, x: u64) -> u64 {
-    debug_assert!(l <= 64);
-    a.wrapping_mul(x) >> (64 - l)
+pub fn shift_u64(l: Bits<64>, a: OddU64, x: u64) -> u64 {
+    a.get().wrapping_mul(x) >> (64 - l.get())
 }
 
 #[inline]
-pub fn shift_u128_128(l: usize, a: u128, x: u128) -> u128 {
-    debug_assert!(l <= 128);
-    a.wrapping_mul(x) >> (128 - l)
+pub fn shift_u128_128(l: Bits<128>, a: OddU128, x: u128) -> u128 {
+    a.get().wrapping_mul(x) >> (128 - l.get())
 }
 
 #[inline]
-pub fn shift_strong_u32(l: usize, a: u64, b: u64, x: u32) -> u32 {
-    debug_assert!(l <= 32);
-    (a.wrapping_mul(u64::from(x)).wrapping_add(b) >> (64 - l)) as u32
+pub fn shift_strong_u32(l: Bits<32>, a: u64, b: u64, x: u32) -> u32 {
+    (a.wrapping_mul(u64::from(x)).wrapping_add(b) >> (64 - l.get())) as u32
 }
 
 #[inline]
-pub fn shift_strong_u64_128(l: usize, a: u128, b: u128, x: u64) -> u64 {
-    debug_assert!(l <= 64);
-    (a.wrapping_mul(u128::from(x)).wrapping_add(b) >> (128 - l)) as u64
+pub fn shift_strong_u64_128(l: Bits<64>, a: u128, b: u128, x: u64) -> u64 {
+    (a.wrapping_mul(u128::from(x)).wrapping_add(b) >> (128 - l.get())) as u64
 }
 
 ////////////////////////////////////////
@@ -118,7 +216,8 @@ pub fn shift_strong_u64_128(l: usize, a: u128, b: u128, x: u64) -> u64 {
 ////////////////////////////////////////
 
 // Interface: u = 2^32, d = 64, m = 2^l, l <= 32
-// Parameters: a[i] < 2^32
+// Parameters: a[i] < 2^64
+// Panics: in debug builds, on a write past d words, before changing the state.
 
 pub struct VectorShiftU32D64 {
     a: [u64; 65],
@@ -134,6 +233,7 @@ impl VectorShiftU32D64 {
 
     #[inline]
     pub fn write_u32(&mut self, x: u32) {
+        debug_assert!(!self.is_done(), "more than d = 64 words");
         let prod = self.a[self.i].wrapping_mul(u64::from(x));
         self.state = self.state.wrapping_add(prod);
         self.i += 1;
@@ -145,17 +245,22 @@ impl VectorShiftU32D64 {
     }
 
     #[inline]
-    pub fn finish(&mut self, l: usize) -> u32 {
-        debug_assert!(l <= 32);
-        let value = (self.state.wrapping_add(self.a[self.i]) >> (64 - l)) as u32;
+    pub fn finish(&mut self, l: Bits<32>) -> u32 {
+        let value = (self.state.wrapping_add(self.a[self.i]) >> (64 - l.get())) as u32;
+        self.reset();
+        value
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
         self.i = 0;
         self.state = 0;
-        value
     }
 }
 
 // Interface: u = 2^64, d = 32, m = 2^l, l <= 32
 // Parameters: a[i] < 2^64
+// Panics: in debug builds, on a write past d words, before changing the state.
 
 pub struct PairShiftU64D32 {
     a: [u64; 65],
@@ -171,6 +276,7 @@ impl PairShiftU64D32 {
 
     #[inline]
     pub fn write_u64(&mut self, x: u64) {
+        debug_assert!(!self.is_done(), "more than d = 32 words");
         let x0 = x & 0xffffffff;
         let x1 = x >> 32;
         let factor0 = self.a[2 * self.i].wrapping_add(x1);
@@ -186,13 +292,17 @@ impl PairShiftU64D32 {
     }
 
     #[inline]
-    pub fn finish(&mut self, l: usize) -> u32 {
-        debug_assert!(l <= 32);
+    pub fn finish(&mut self, l: Bits<32>) -> u32 {
         let ad = self.a[2 * self.i];
-        let value = (self.state.wrapping_add(ad) >> (64 - l)) as u32;
+        let value = (self.state.wrapping_add(ad) >> (64 - l.get())) as u32;
+        self.reset();
+        value
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
         self.i = 0;
         self.state = 0;
-        value
     }
 }
 
@@ -213,7 +323,8 @@ pub struct PolyU64 {
 
 impl PolyU64 {
     #[inline]
-    pub fn new(a: [u32; 3], b: [u32; 3], c: [u32; 3]) -> Self {
+    pub fn new(a: FieldElem89, b: FieldElem89, c: FieldElem89) -> Self {
+        let (a, b, c) = (a.get(), b.get(), c.get());
         let state = [0, 0, 0];
         Self { a, b, c, state }
     }
@@ -227,13 +338,17 @@ impl PolyU64 {
     }
 
     #[inline]
-    pub fn finish(&mut self, l: usize) -> u64 {
-        debug_assert!(l <= 64);
+    pub fn finish(&mut self, l: Bits<64>) -> u64 {
         let s = mul3x3(self.a, self.state);
         let t = add6x3modp(s, self.b);
         let value = (t[0] as u64) | ((t[1] as u64) << 32);
+        self.reset();
+        value & (!0 >> (64 - l.get()))
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
         self.state = [0, 0, 0];
-        value & ((1 << l) - 1)
     }
 }
 
@@ -250,7 +365,8 @@ pub struct PolyShiftU64 {
 
 impl PolyShiftU64 {
     #[inline]
-    pub fn new(a: [u64; 3], b: [u64; 3], c: [u32; 3]) -> Self {
+    pub fn new(a: [u64; 3], b: [u64; 3], c: FieldElem89) -> Self {
+        let c = c.get();
         let state = [0, 0, 0];
         Self { a, b, c, state }
     }
@@ -264,15 +380,20 @@ impl PolyShiftU64 {
     }
 
     #[inline]
-    pub fn finish(&mut self, l: usize) -> u32 {
+    pub fn finish(&mut self, l: Bits<32>) -> u32 {
         let q0 = shift_strong_u32(l, self.a[0], self.b[0], self.state[0]);
         let q1 = shift_strong_u32(l, self.a[1], self.b[1], self.state[1]);
         let q2 = shift_strong_u32(l, self.a[2], self.b[2], self.state[2]);
 
-        self.state = [0, 0, 0];
+        self.reset();
 
         q0 ^ q1 ^ q2
     }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        self.state = [0, 0, 0];
+    }
 }
 
 // Constants: p = 2^89 - 1, d = 32
@@ -287,7 +408,13 @@ pub struct PreprocPolyU64D32 {
 
 impl PreprocPolyU64D32 {
     #[inline]
-    pub fn new(prep1: [u64; 65], prep2: [u64; 65], a: [u32; 3], b: [u32; 3], c: [u32; 3]) -> Self {
+    pub fn new(
+        prep1: [u64; 65],
+        prep2: [u64; 65],
+        a: FieldElem89,
+        b: FieldElem89,
+        c: FieldElem89,
+    ) -> Self {
         Self {
             prep1: PairShiftU64D32::new(prep1),
             prep2: PairShiftU64D32::new(prep2),
@@ -305,20 +432,54 @@ impl PreprocPolyU64D32 {
     }
 
     #[inline]
-    pub fn finish(&mut self, l: usize) -> u64 {
+    pub fn finish(&mut self, l: Bits<64>) -> u64 {
         self.flush();
         self.poly.finish(l)
     }
 
+    #[inline]
+    pub fn reset(&mut self) {
+        self.prep1.reset();
+        self.prep2.reset();
+        self.poly.reset();
+    }
+
     #[inline]
     fn flush(&mut self) {
-        let q1 = self.prep1.finish(32);
-        let q2 = self.prep2.finish(32);
+        let q1 = self.prep1.finish(Bits::MAX);
+        let q2 = self.prep2.finish(Bits::MAX);
         let q = (q1 as u64) | (q2 as u64) << 32;
         self.poly.write_u64(q);
     }
 }
 
+////////////////////////////////////////
+// Simple Tabulation
+////////////////////////////////////////
+
+// Interface: u = 2^64, c = 8, m = 2^l, l <= 64
+// Parameters: t[i][j] < 2^64
+
+pub struct TabulationU64 {
+    t: Box<[[u64; 256]; 8]>,
+}
+
+impl TabulationU64 {
+    #[inline]
+    pub fn new(t: Box<[[u64; 256]; 8]>) -> Self {
+        Self { t }
+    }
+
+    #[inline]
+    pub fn hash(&self, l: Bits<64>, x: u64) -> u64 {
+        let mut h = 0;
+        for (i, t) in self.t.iter().enumerate() {
+            h ^= t[(x >> (8 * i)) as u8 as usize];
+        }
+        h >> (64 - l.get())
+    }
+}
+
 ////////////////////////////////////////
 // Helper Functions
 ////////////////////////////////////////
@@ -397,3 +558,578 @@ pub fn add6x3modp(a: [u32; 6], b: [u32; 3]) -> [u32; 3] {
     let g = trysubp3(f);
     g
 }
+
+////////////////////////////////////////
+// Tests
+////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cmp::Ordering;
+
+    use reference::{self, BigUint};
+    use rng::SplitMix64;
+
+    const P_MINUS_1: [u32; 3] = [0xfffffffe, 0xffffffff, 0x01ffffff];
+
+    const EDGE_KEYS: [[u32; 3]; 5] = [
+        [0, 0, 0],
+        [1, 0, 0],
+        [0xffffffff, 0xffffffff, 0],
+        [0, 0, 0x01000000],
+        P_MINUS_1,
+    ];
+
+    const EDGE_WORDS: [u64; 6] = [0, 1, 0xffffffff, 1 << 63, !0 - 1, !0];
+
+    // Mostly random keys below p, sometimes an edge case.
+    fn key(rng: &mut SplitMix64) -> [u32; 3] {
+        match rng.below_u64(8) {
+            0 => EDGE_KEYS[rng.below_u64(EDGE_KEYS.len() as u64) as usize],
+            _ => rng.below_m89().get(),
+        }
+    }
+
+    fn elem(x: [u32; 3]) -> FieldElem89 {
+        FieldElem89::new(x).unwrap()
+    }
+
+    fn word(rng: &mut SplitMix64) -> u64 {
+        match rng.below_u64(4) {
+            0 => EDGE_WORDS[rng.below_u64(EDGE_WORDS.len() as u64) as usize],
+            _ => rng.next_u64(),
+        }
+    }
+
+    fn message(rng: &mut SplitMix64, max_len: u64) -> Vec<u64> {
+        (0..rng.below_u64(max_len + 1)).map(|_| word(rng)).collect()
+    }
+
+    fn bits(rng: &mut SplitMix64, max: u64) -> usize {
+        rng.below_u64(max) as usize + 1
+    }
+
+    #[test]
+    fn parameters_are_checked() {
+        assert_eq!(Bits::<32>::new(0), None);
+        assert_eq!(Bits::<32>::new(32).map(Bits::get), Some(32));
+        assert_eq!(Bits::<32>::new(33), None);
+        assert_eq!(OddU64::new(1 << 63), None);
+        assert_eq!(OddU64::new(!0).map(OddU64::get), Some(!0));
+        assert_eq!(
+            FieldElem31::new(M31 - 1).map(FieldElem31::get),
+            Some(M31 - 1)
+        );
+        assert_eq!(FieldElem31::new(M31), None);
+        assert_eq!(FieldElem61::new(M61), None);
+        assert_eq!(
+            FieldElem89::new(P_MINUS_1).map(FieldElem89::get),
+            Some(P_MINUS_1)
+        );
+        assert_eq!(FieldElem89::new(M89), None);
+        assert_eq!(FieldElem89::new([0, 0, 0x02000000]), None);
+        assert_eq!(FieldElem89::new([!0; 3]), None);
+    }
+
+    #[test]
+    fn reduce_matches_reference() {
+        let mut rng = SplitMix64::new(0x3c6ef372fe94f82b);
+        for i in 0..1 << 16 {
+            let x = match i {
+                0 => [!0, !0, !0],
+                1 => M89,
+                2 => [0, 0, 0x02000000],
+                _ => [rng.next_u32(), rng.next_u32(), rng.next_u32()],
+            };
+            let expected = BigUint::from_limbs(&x).rem(&reference::p89());
+            let q = FieldElem89::reduce(x).get();
+            assert_eq!(BigUint::from_limbs(&q), expected, "{:?}", x);
+        }
+    }
+
+    #[test]
+    fn mul3x3_matches_reference() {
+        let mut rng = SplitMix64::new(0x1d8e4e27c47d124f);
+        for _ in 0..1 << 18 {
+            let a = key(&mut rng);
+            let x = key(&mut rng);
+            let expected = BigUint::from_limbs(&a).mul(&BigUint::from_limbs(&x));
+            assert_eq!(
+                BigUint::from_limbs(&mul3x3(a, x)),
+                expected,
+                "{:?} {:?}",
+                a,
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn add6x3modp_matches_reference() {
+        let p = reference::p89();
+        let mut rng = SplitMix64::new(0x9e6c63d0676a9a99);
+        for _ in 0..1 << 18 {
+            let a = mul3x3(key(&mut rng), key(&mut rng));
+            let b = key(&mut rng);
+            let expected = BigUint::from_limbs(&a)
+                .add(&BigUint::from_limbs(&b))
+                .rem(&p);
+            let actual = add6x3modp(a, b);
+            assert_eq!(BigUint::from_limbs(&actual), expected, "{:?} {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn mmp_p89_u64_edge_cases() {
+        for &a in &EDGE_KEYS {
+            for &b in &EDGE_KEYS {
+                for &x in &EDGE_WORDS {
+                    for &l in &[1, 32, 63, 64] {
+                        let expected = reference::mmp_p89_u64(l, a, b, x);
+                        assert_eq!(
+                            mmp_p89_u64(Bits::new(l).unwrap(), elem(a), elem(b), x),
+                            expected,
+                            "{:?} {:?} {}",
+                            a,
+                            b,
+                            x
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mmp_p89_u64_matches_reference() {
+        let mut rng = SplitMix64::new(0x5851f42d4c957f2d);
+        for _ in 0..1 << 21 {
+            let (a, b, x) = (key(&mut rng), key(&mut rng), word(&mut rng));
+            let l = bits(&mut rng, 64);
+            let expected = reference::mmp_p89_u64(l, a, b, x);
+            assert_eq!(
+                mmp_p89_u64(Bits::new(l).unwrap(), elem(a), elem(b), x),
+                expected,
+                "{:?} {:?} {}",
+                a,
+                b,
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn poly_u64_matches_reference() {
+        let mut rng = SplitMix64::new(0x14057b7ef767814f);
+        for _ in 0..1 << 15 {
+            let (a, b, c) = (key(&mut rng), key(&mut rng), key(&mut rng));
+            let xs = message(&mut rng, 64);
+            let l = bits(&mut rng, 64);
+
+            let mut h = PolyU64::new(elem(a), elem(b), elem(c));
+            for &x in &xs {
+                h.write_u64(x);
+            }
+            let expected = reference::poly_u64(l, a, b, c, &xs);
+            assert_eq!(
+                h.finish(Bits::new(l).unwrap()),
+                expected,
+                "{:?} {:?} {:?} {:?}",
+                a,
+                b,
+                c,
+                xs
+            );
+        }
+    }
+
+    #[test]
+    fn poly_u64_largest_state() {
+        // With c = p - 1 and all-ones words the state stays near p.
+        for len in 0..64 {
+            let xs = vec![!0; len];
+            let mut h = PolyU64::new(elem(P_MINUS_1), elem(P_MINUS_1), elem(P_MINUS_1));
+            for &x in &xs {
+                h.write_u64(x);
+            }
+            let expected = reference::poly_u64(64, P_MINUS_1, P_MINUS_1, P_MINUS_1, &xs);
+            assert_eq!(h.finish(Bits::MAX), expected, "{}", len);
+        }
+    }
+
+    #[test]
+    fn poly_shift_u64_matches_reference() {
+        let mut rng = SplitMix64::new(0xda942042e4dd58b5);
+        for _ in 0..1 << 15 {
+            let a = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+            let b = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+            let c = key(&mut rng);
+            let xs = message(&mut rng, 64);
+            let l = bits(&mut rng, 32);
+
+            let mut h = PolyShiftU64::new(a, b, elem(c));
+            for &x in &xs {
+                h.write_u64(x);
+            }
+            let expected = reference::poly_shift_u64(l, a, b, c, &xs);
+            assert_eq!(
+                h.finish(Bits::new(l).unwrap()),
+                expected,
+                "{:?} {:?} {:?} {:?}",
+                a,
+                b,
+                c,
+                xs
+            );
+        }
+    }
+
+    #[test]
+    fn preproc_poly_u64_matches_reference() {
+        let mut rng = SplitMix64::new(0x2545f4914f6cdd1d);
+        for _ in 0..1 << 12 {
+            let mut prep1 = [0; 65];
+            let mut prep2 = [0; 65];
+            rng.fill_u64(&mut prep1);
+            rng.fill_u64(&mut prep2);
+            let (a, b, c) = (key(&mut rng), key(&mut rng), key(&mut rng));
+            let xs = message(&mut rng, 100);
+            let l = bits(&mut rng, 64);
+
+            let mut h = PreprocPolyU64D32::new(prep1, prep2, elem(a), elem(b), elem(c));
+            for &x in &xs {
+                h.write_u64(x);
+            }
+            let expected = reference::preproc_poly_u64(l, (&prep1, &prep2), a, b, c, &xs);
+            assert_eq!(
+                h.finish(Bits::new(l).unwrap()),
+                expected,
+                "{:?} {:?} {:?} {:?}",
+                a,
+                b,
+                c,
+                xs
+            );
+        }
+    }
+    #[test]
+    fn shift_matches_reference() {
+        let mut rng = SplitMix64::new(0x9e3779b97f4a7c15);
+        for _ in 0..1 << 16 {
+            let (a, x) = (word(&mut rng) | 1, word(&mut rng));
+            let l = bits(&mut rng, 32);
+            assert_eq!(
+                shift_u32(
+                    Bits::new(l).unwrap(),
+                    OddU32::new(a as u32).unwrap(),
+                    x as u32
+                ),
+                reference::shift_u32(l, a as u32, x as u32),
+                "{} {}",
+                a as u32,
+                x as u32
+            );
+            let l = bits(&mut rng, 64);
+            assert_eq!(
+                shift_u64(Bits::new(l).unwrap(), OddU64::new(a).unwrap(), x),
+                reference::shift_u64(l, a, x),
+                "{} {}",
+                a,
+                x
+            );
+            let a = u128::from(word(&mut rng)) << 64 | u128::from(a);
+            let x = u128::from(word(&mut rng)) << 64 | u128::from(x);
+            let l = bits(&mut rng, 128);
+            assert_eq!(
+                shift_u128_128(Bits::new(l).unwrap(), OddU128::new(a).unwrap(), x),
+                reference::shift_u128_128(l, a, x),
+                "{} {}",
+                a,
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn shift_strong_matches_reference() {
+        let mut rng = SplitMix64::new(0xbf58476d1ce4e5b9);
+        for _ in 0..1 << 16 {
+            let (a, b, x) = (word(&mut rng), word(&mut rng), word(&mut rng));
+            let l = bits(&mut rng, 32);
+            assert_eq!(
+                shift_strong_u32(Bits::new(l).unwrap(), a, b, x as u32),
+                reference::shift_strong_u32(l, a, b, x as u32),
+                "{} {} {}",
+                a,
+                b,
+                x as u32
+            );
+            let a = u128::from(word(&mut rng)) << 64 | u128::from(a);
+            let b = u128::from(word(&mut rng)) << 64 | u128::from(b);
+            let l = bits(&mut rng, 64);
+            assert_eq!(
+                shift_strong_u64_128(Bits::new(l).unwrap(), a, b, x),
+                reference::shift_strong_u64_128(l, a, b, x),
+                "{} {} {}",
+                a,
+                b,
+                x
+            );
+        }
+    }
+
+    // Mostly random elements below p = 2^31 - 1, sometimes 0, 1 or p - 1.
+    fn elem31(rng: &mut SplitMix64) -> u32 {
+        match rng.below_u64(8) {
+            0 => [0, 1, M31 - 1][rng.below_u64(3) as usize],
+            _ => rng.below_m31().get(),
+        }
+    }
+
+    // Mostly random elements below p = 2^61 - 1, sometimes 0, 1 or p - 1.
+    fn elem61(rng: &mut SplitMix64) -> u64 {
+        match rng.below_u64(8) {
+            0 => [0, 1, M61 - 1][rng.below_u64(3) as usize],
+            _ => rng.below_m61().get(),
+        }
+    }
+
+    #[test]
+    fn mmp_p31_matches_reference() {
+        let mut rng = SplitMix64::new(0x94d049bb133111eb);
+        for _ in 0..1 << 16 {
+            let (a, b, x) = (elem31(&mut rng), elem31(&mut rng), elem31(&mut rng));
+            let l = bits(&mut rng, 30);
+            let (fa, fb) = (FieldElem31::new(a).unwrap(), FieldElem31::new(b).unwrap());
+            assert_eq!(
+                mmp_p31_u30(Bits::new(l).unwrap(), fa, fb, x),
+                reference::mmp_p31_u30(l, a, b, x),
+                "{} {} {}",
+                a,
+                b,
+                x
+            );
+
+            let a = [elem31(&mut rng), elem31(&mut rng), elem31(&mut rng)];
+            let b = [elem31(&mut rng), elem31(&mut rng), elem31(&mut rng)];
+            let x = word(&mut rng);
+            let fa = a.map(|a| FieldElem31::new(a).unwrap());
+            let fb = b.map(|b| FieldElem31::new(b).unwrap());
+            assert_eq!(
+                mmp_p31_u64(Bits::new(l).unwrap(), fa, fb, x),
+                reference::mmp_p31_u64(l, a, b, x),
+                "{:?} {:?} {}",
+                a,
+                b,
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn mmp_p61_u60_128_matches_reference() {
+        let mut rng = SplitMix64::new(0xd6e8feb86659fd93);
+        for _ in 0..1 << 16 {
+            let (a, b, x) = (elem61(&mut rng), elem61(&mut rng), elem61(&mut rng));
+            let l = bits(&mut rng, 60);
+            let (fa, fb) = (FieldElem61::new(a).unwrap(), FieldElem61::new(b).unwrap());
+            assert_eq!(
+                mmp_p61_u60_128(Bits::new(l).unwrap(), fa, fb, x),
+                reference::mmp_p61_u60(l, a, b, x),
+                "{} {} {}",
+                a,
+                b,
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn vector_shift_u32_matches_reference() {
+        let mut rng = SplitMix64::new(0xa0761d6478bd642f);
+        for _ in 0..1 << 12 {
+            let mut a = [0; 65];
+            rng.fill_u64(&mut a);
+            let xs = message(&mut rng, 64)
+                .into_iter()
+                .map(|x| x as u32)
+                .collect::<Vec<_>>();
+            let l = bits(&mut rng, 32);
+
+            let mut h = VectorShiftU32D64::new(a);
+            for &x in &xs {
+                h.write_u32(x);
+            }
+            let expected = reference::vector_shift_u32(l, &a, &xs);
+            assert_eq!(h.finish(Bits::new(l).unwrap()), expected, "{:?}", xs);
+        }
+    }
+
+    #[test]
+    fn pair_shift_u64_matches_reference() {
+        let mut rng = SplitMix64::new(0xe7037ed1a0b428db);
+        for _ in 0..1 << 12 {
+            let mut a = [0; 65];
+            rng.fill_u64(&mut a);
+            let xs = message(&mut rng, 32);
+            let l = bits(&mut rng, 32);
+
+            let mut h = PairShiftU64D32::new(a);
+            for &x in &xs {
+                h.write_u64(x);
+            }
+            let expected = reference::pair_shift_u64(l, &a, &xs);
+            assert_eq!(h.finish(Bits::new(l).unwrap()), expected, "{:?}", xs);
+        }
+    }
+
+    #[test]
+    fn tabulation_u64_matches_reference() {
+        let mut rng = SplitMix64::new(0x8ebc6af09c88c6e3);
+        for _ in 0..1 << 6 {
+            let mut t = Box::new([[0; 256]; 8]);
+            for row in t.iter_mut() {
+                rng.fill_u64(row);
+            }
+            let expected = (0..1 << 8)
+                .map(|_| {
+                    let (x, l) = (word(&mut rng), bits(&mut rng, 64));
+                    (x, l, reference::tabulation_u64(l, &t, x))
+                })
+                .collect::<Vec<_>>();
+
+            let h = TabulationU64::new(t);
+            for (x, l, expected) in expected {
+                assert_eq!(h.hash(Bits::new(l).unwrap(), x), expected, "{}", x);
+            }
+        }
+    }
+
+    // Properties: algebraic invariants of the helpers, checked against arithmetic
+    // written out independently of the reference module.
+
+    // Uniform below 2^bits as 32-bit limbs, sometimes all ones.
+    fn limbs<A: AsMut<[u32]> + Default>(rng: &mut SplitMix64, bits: usize) -> A {
+        let all_ones = rng.below_u64(8) == 0;
+        let mut x = A::default();
+        for (i, limb) in x.as_mut().iter_mut().enumerate() {
+            let width = bits.saturating_sub(32 * i).min(32);
+            let mask = if width == 32 { !0 } else { (1 << width) - 1 };
+            *limb = if all_ones { !0 } else { rng.next_u32() } & mask;
+        }
+        x
+    }
+
+    fn below_p(x: [u32; 3]) -> bool {
+        x.iter().rev().cmp(M89.iter().rev()) == Ordering::Less
+    }
+
+    fn mulmodp(a: [u32; 3], b: [u32; 3]) -> [u32; 3] {
+        add6x3modp(mul3x3(a, b), [0, 0, 0])
+    }
+
+    fn addmodp(a: [u32; 3], b: [u32; 3]) -> [u32; 3] {
+        add6x3modp([a[0], a[1], a[2], 0, 0, 0], b)
+    }
+
+    #[test]
+    fn add6x3modp_is_canonical() {
+        // Any a < 2^178, which includes all products of two values below p.
+        let mut rng = SplitMix64::new(0x6a09e667f3bcc909);
+        for _ in 0..1 << 20 {
+            let a: [u32; 6] = limbs(&mut rng, 178);
+            let b = key(&mut rng);
+            let r = add6x3modp(a, b);
+            assert!(below_p(r), "{:?} {:?} {:?}", a, b, r);
+        }
+        assert!(below_p(add6x3modp(mul3x3(P_MINUS_1, P_MINUS_1), P_MINUS_1)));
+    }
+
+    #[test]
+    fn add6x3modp_is_identity_on_reduced() {
+        let mut rng = SplitMix64::new(0xbb67ae8584caa73b);
+        for _ in 0..1 << 20 {
+            let x = key(&mut rng);
+            assert_eq!(addmodp(x, [0, 0, 0]), x);
+            assert_eq!(mulmodp(x, [1, 0, 0]), x);
+        }
+    }
+
+    #[test]
+    fn mul3x3_matches_column_product() {
+        // Column sums of at most three 64-bit partial products fit in u128.
+        let mut rng = SplitMix64::new(0x3c6ef372fe94f82b);
+        for _ in 0..1 << 20 {
+            let a: [u32; 3] = limbs(&mut rng, 96);
+            let x: [u32; 3] = limbs(&mut rng, 96);
+            let mut expected = [0; 6];
+            let mut carry = 0u128;
+            for (k, limb) in expected.iter_mut().enumerate() {
+                let mut sum = carry;
+                for i in k.saturating_sub(2)..=k.min(2) {
+                    sum += u128::from(u64::from(a[i]) * u64::from(x[k - i]));
+                }
+                *limb = sum as u32;
+                carry = sum >> 32;
+            }
+            assert_eq!(carry, 0);
+            assert_eq!(mul3x3(a, x), expected, "{:?} {:?}", a, x);
+        }
+    }
+
+    #[test]
+    fn trysubp3_is_idempotent_on_reduced() {
+        let mut rng = SplitMix64::new(0xa54ff53a5f1d36f1);
+        for _ in 0..1 << 20 {
+            let x = key(&mut rng);
+            assert_eq!(trysubp3(x), x);
+            // x + p < 2p reduces to x in one step, and stays there.
+            let y = add3x3x3_no_overflow(x, M89, [0, 0, 0]);
+            assert_eq!(trysubp3(y), x);
+            assert_eq!(trysubp3(trysubp3(y)), x);
+        }
+    }
+
+    #[test]
+    fn poly_u64_special_points() {
+        // With a = 1 and c = 0, 1 or p - 1 the hash is the last word, the sum or the
+        // alternating sum of the message, plus b, which u128 arithmetic gets right.
+        let p = u128::from(M89[0]) | u128::from(M89[1]) << 32 | u128::from(M89[2]) << 64;
+        let mut rng = SplitMix64::new(0x510e527fade682d1);
+        for _ in 0..1 << 14 {
+            let b = key(&mut rng);
+            let xs = message(&mut rng, 64);
+            let c = match rng.below_u64(3) {
+                0 => [0, 0, 0],
+                1 => [1, 0, 0],
+                _ => P_MINUS_1,
+            };
+
+            let mut h = PolyU64::new(elem([1, 0, 0]), elem(b), elem(c));
+            for &x in &xs {
+                h.write_u64(x);
+            }
+
+            let sum = match c[0] {
+                0 => xs.last().map_or(0, |&x| u128::from(x)),
+                1 => xs.iter().map(|&x| u128::from(x)).sum(),
+                _ => {
+                    let sum = xs.iter().rev().enumerate().fold(0i128, |sum, (i, &x)| {
+                        if i % 2 == 0 {
+                            sum + i128::from(x)
+                        } else {
+                            sum - i128::from(x)
+                        }
+                    });
+                    sum.rem_euclid(p as i128) as u128
+                }
+            };
+            let b = u128::from(b[0]) | u128::from(b[1]) << 32 | u128::from(b[2]) << 64;
+            let expected = ((sum + b) % p) as u64;
+
+            assert_eq!(h.finish(Bits::MAX), expected, "{:?} {:?} {:?}", b, c, xs);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use rng::SplitMix64;
+
+////////////////////////////////////////
+// Input Sources
+////////////////////////////////////////
+
+// Description: Where the raw bytes for an experiment come from. The synthetic
+// generators produce exactly the requested number of bytes; experiments read them
+// as big-endian words like they read input.txt.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    File(String),
+    Stdin,
+    // Uniformly random bytes.
+    Uniform,
+    // Big-endian 64-bit counters 0, 1, 2, ...
+    Sequential,
+    // Big-endian 64-bit values drawn from [0, 2^16), so few distinct keys.
+    Dense,
+    // One random 64-byte block, repeated.
+    Repeat,
+}
+
+pub const DEFAULT_SIZE: usize = 1 << 20;
+
+const SEED: u64 = 0x6a09e667f3bcc908;
+
+impl Source {
+    // A file, with "-" meaning stdin.
+    pub fn path(path: &str) -> Self {
+        match path {
+            "-" => Source::Stdin,
+            path => Source::File(path.to_string()),
+        }
+    }
+
+    pub fn generator(name: &str) -> Option<Self> {
+        match name {
+            "uniform" => Some(Source::Uniform),
+            "sequential" => Some(Source::Sequential),
+            "dense" => Some(Source::Dense),
+            "repeat" => Some(Source::Repeat),
+            _ => None,
+        }
+    }
+
+    pub fn read(&self, size: usize) -> io::Result<Vec<u8>> {
+        let mut raw = Vec::new();
+        match *self {
+            Source::File(ref path) => {
+                File::open(path)?.read_to_end(&mut raw)?;
+            }
+            Source::Stdin => {
+                io::stdin().read_to_end(&mut raw)?;
+            }
+            Source::Uniform => {
+                let mut rng = SplitMix64::new(SEED);
+                raw = vec![0; (size + 7) & !7];
+                for chunk in raw.chunks_mut(8) {
+                    BigEndian::write_u64(chunk, rng.next_u64());
+                }
+                raw.truncate(size);
+            }
+            Source::Sequential => {
+                raw = vec![0; (size + 7) & !7];
+                for (i, chunk) in raw.chunks_mut(8).enumerate() {
+                    BigEndian::write_u64(chunk, i as u64);
+                }
+                raw.truncate(size);
+            }
+            Source::Dense => {
+                let mut rng = SplitMix64::new(SEED);
+                raw = vec![0; (size + 7) & !7];
+                for chunk in raw.chunks_mut(8) {
+                    BigEndian::write_u64(chunk, rng.below_u64(1 << 16));
+                }
+                raw.truncate(size);
+            }
+            Source::Repeat => {
+                let mut rng = SplitMix64::new(SEED);
+                let mut block = [0; 64];
+                for chunk in block.chunks_mut(8) {
+                    BigEndian::write_u64(chunk, rng.next_u64());
+                }
+                raw = block.iter().cloned().cycle().take(size).collect();
+            }
+        }
+        Ok(raw)
+    }
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Source::File("input.txt".to_string())
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::File(ref path) => write!(f, "{}", path),
+            Source::Stdin => write!(f, "-"),
+            Source::Uniform => write!(f, "uniform"),
+            Source::Sequential => write!(f, "sequential"),
+            Source::Dense => write!(f, "dense"),
+            Source::Repeat => write!(f, "repeat"),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use OutputMode;
+
+////////////////////////////////////////
+// JSON Output
+////////////////////////////////////////
+
+// Description: Results as JSON. OutputMode::Json prints one document holding the
+// run metadata and an array of result rows; OutputMode::JsonLines prints one
+// object per row with the metadata fields repeated, so every line stands alone.
+// The metadata is set once by begin before any row is printed.
+
+#[derive(Clone, Copy, Debug)]
+pub enum Value<'a> {
+    Str(&'a str),
+    Num(f64),
+    Bool(bool),
+    Null,
+}
+
+impl<'a> Value<'a> {
+    pub fn opt(value: Option<f64>) -> Self {
+        match value {
+            Some(x) => Value::Num(x),
+            None => Value::Null,
+        }
+    }
+}
+
+pub type Fields<'a> = [(&'a str, Value<'a>)];
+
+static META: OnceLock<String> = OnceLock::new();
+static FIRST: AtomicBool = AtomicBool::new(true);
+
+pub fn begin(mode: OutputMode, meta: &Fields) {
+    let meta = META.get_or_init(|| members(meta));
+    if let OutputMode::Json = mode {
+        println!("{{\"meta\":{{{}}},\"results\":[", meta);
+    }
+}
+
+pub fn end(mode: OutputMode) {
+    if let OutputMode::Json = mode {
+        println!("]}}");
+    }
+}
+
+pub fn row(mode: OutputMode, fields: &Fields) {
+    let row = members(fields);
+    match mode {
+        OutputMode::Json => {
+            let sep = if FIRST.swap(false, Ordering::Relaxed) {
+                ""
+            } else {
+                ","
+            };
+            println!("{}{{{}}}", sep, row);
+        }
+        OutputMode::JsonLines => match META.get() {
+            Some(meta) if !meta.is_empty() => println!("{{{},{}}}", row, meta),
+            _ => println!("{{{}}}", row),
+        },
+        _ => unreachable!(),
+    }
+}
+
+////////////////////////////////////////
+// Helper Functions
+////////////////////////////////////////
+
+fn members(fields: &Fields) -> String {
+    let members = fields
+        .iter()
+        .map(|&(key, value)| format!("{}:{}", string(key), encode(value)))
+        .collect::<Vec<_>>();
+    members.join(",")
+}
+
+fn encode(value: Value) -> String {
+    match value {
+        Value::Str(s) => string(s),
+        Value::Num(x) if x.is_finite() => format!("{}", x),
+        Value::Num(_) | Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("{}", b),
+    }
+}
+
+fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
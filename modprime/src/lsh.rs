@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use minhash;
+
+////////////////////////////////////////
+// Banded LSH
+////////////////////////////////////////
+
+// Description: Locality-sensitive hashing index over MinHash signatures. Signatures
+// are cut into b bands of r rows; two sets become candidates when any band agrees,
+// which happens with probability 1 - (1 - s^r)^b for Jaccard similarity s.
+// Interface: signatures have exactly b * r values.
+// Parameters: b, r >= 1
+
+pub struct LshIndex {
+    bands: usize,
+    rows: usize,
+    buckets: Vec<HashMap<Vec<u64>, Vec<usize>>>,
+    signatures: Vec<Vec<u64>>,
+}
+
+impl LshIndex {
+    pub fn new(bands: usize, rows: usize) -> Self {
+        assert!(bands > 0 && rows > 0);
+        let buckets = (0..bands).map(|_| HashMap::new()).collect();
+        let signatures = Vec::new();
+        Self {
+            bands,
+            rows,
+            buckets,
+            signatures,
+        }
+    }
+
+    // Picks the split of k signature values whose S-curve threshold is closest to t.
+    // Returns None unless 0 < t < 1.
+    pub fn with_threshold(k: usize, t: f64) -> Option<Self> {
+        let (bands, rows) = params_for_threshold(k, t)?;
+        Some(Self::new(bands, rows))
+    }
+
+    #[inline]
+    pub fn bands(&self) -> usize {
+        self.bands
+    }
+
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    // Similarity at which the candidate probability rises most steeply.
+    #[inline]
+    pub fn threshold(&self) -> f64 {
+        threshold(self.bands, self.rows)
+    }
+
+    // Probability that two sets with Jaccard similarity s become candidates.
+    #[inline]
+    pub fn probability(&self, s: f64) -> f64 {
+        1.0 - (1.0 - s.powi(self.rows as i32)).powi(self.bands as i32)
+    }
+
+    #[inline]
+    pub fn signature(&self, id: usize) -> &[u64] {
+        &self.signatures[id]
+    }
+
+    // Returns the id of the inserted signature; ids are assigned consecutively.
+    pub fn insert(&mut self, signature: Vec<u64>) -> usize {
+        assert_eq!(signature.len(), self.bands * self.rows);
+        let id = self.signatures.len();
+        for (band, bucket) in signature.chunks(self.rows).zip(&mut self.buckets) {
+            bucket
+                .entry(band.to_vec())
+                .or_insert_with(Vec::new)
+                .push(id);
+        }
+        self.signatures.push(signature);
+        id
+    }
+
+    // Ids sharing at least one band with the signature, in increasing order.
+    pub fn query(&self, signature: &[u64]) -> Vec<usize> {
+        assert_eq!(signature.len(), self.bands * self.rows);
+        let mut ids = Vec::new();
+        for (band, bucket) in signature.chunks(self.rows).zip(&self.buckets) {
+            if let Some(found) = bucket.get(band) {
+                ids.extend_from_slice(found);
+            }
+        }
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    // Candidates whose estimated Jaccard similarity is at least min, with the estimate.
+    pub fn near_duplicates(&self, signature: &[u64], min: f64) -> Vec<(usize, f64)> {
+        self.query(signature)
+            .into_iter()
+            .map(|id| (id, minhash::jaccard(signature, &self.signatures[id])))
+            .filter(|&(_, s)| s >= min)
+            .collect()
+    }
+}
+
+#[inline]
+pub fn threshold(bands: usize, rows: usize) -> f64 {
+    (1.0 / bands as f64).powf(1.0 / rows as f64)
+}
+
+// Returns None unless 0 < t < 1, which also rules out NaN.
+pub fn params_for_threshold(k: usize, t: f64) -> Option<(usize, usize)> {
+    assert!(k > 0);
+    if !(0.0 < t && t < 1.0) {
+        return None;
+    }
+    (1..=k)
+        .filter(|&rows| (k / rows) * rows == k)
+        .map(|rows| (k / rows, rows))
+        .min_by(|&(b0, r0), &(b1, r1)| {
+            let d0 = (threshold(b0, r0) - t).abs();
+            let d1 = (threshold(b1, r1) - t).abs();
+            d0.total_cmp(&d1)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_finds_shared_bands() {
+        let mut index = LshIndex::new(4, 2);
+        assert!(index.is_empty());
+        let a = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let b = vec![1, 2, 0, 0, 0, 0, 0, 0];
+        let c = vec![0, 2, 0, 4, 0, 6, 0, 8];
+        assert_eq!(index.insert(a.clone()), 0);
+        assert_eq!(index.insert(b.clone()), 1);
+        assert_eq!(index.insert(c.clone()), 2);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.signature(1), &b[..]);
+
+        // b shares the first band with a and the other three with itself only; c
+        // agrees with a in every other row but in no whole band.
+        assert_eq!(index.query(&a), vec![0, 1]);
+        assert_eq!(index.query(&b), vec![0, 1]);
+        assert_eq!(index.query(&c), vec![2]);
+        assert_eq!(index.query(&[9; 8]), vec![]);
+
+        assert_eq!(index.near_duplicates(&a, 0.5), vec![(0, 1.0)]);
+        assert_eq!(index.near_duplicates(&a, 0.25), vec![(0, 1.0), (1, 0.25)]);
+    }
+
+    #[test]
+    fn threshold_is_steepest_point() {
+        for &(bands, rows) in &[(32, 2), (16, 4), (8, 8), (20, 5)] {
+            let index = LshIndex::new(bands, rows);
+            let t = index.threshold();
+            assert!((t.powi(rows as i32) * bands as f64 - 1.0).abs() < 1e-9);
+            assert_eq!(index.probability(0.0), 0.0);
+            assert_eq!(index.probability(1.0), 1.0);
+            assert!(index.probability(t - 0.1) < index.probability(t));
+            assert!(index.probability(t) < index.probability(t + 0.1));
+        }
+    }
+
+    #[test]
+    fn params_are_closest_split() {
+        for &k in &[1, 12, 64, 100, 128] {
+            for &t in &[0.05, 0.3, 0.5, 0.8, 0.95] {
+                let index = LshIndex::with_threshold(k, t).unwrap();
+                assert_eq!(index.bands() * index.rows(), k);
+                let best = (1..=k)
+                    .filter(|&rows| k % rows == 0)
+                    .map(|rows| (threshold(k / rows, rows) - t).abs())
+                    .fold(f64::INFINITY, f64::min);
+                assert_eq!((index.threshold() - t).abs(), best);
+            }
+        }
+    }
+
+    #[test]
+    fn params_reject_bad_threshold() {
+        for &t in &[0.0, 1.0, -0.5, 1.5, f64::NAN, f64::INFINITY] {
+            assert_eq!(params_for_threshold(64, t), None);
+            assert!(LshIndex::with_threshold(64, t).is_none());
+        }
+    }
+}
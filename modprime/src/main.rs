@@ -1,12 +1,20 @@
-#![feature(test, sip_hash_13)]
+#![feature(test)]
 
 extern crate byteorder;
+#[cfg(target_os = "linux")]
+extern crate libc;
 extern crate test;
 
+use std::cmp;
+use std::collections::HashSet;
 use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
+use std::ops::BitXor;
 use std::process;
+use std::sync::Barrier;
+use std::thread;
 use std::time::Instant;
 
 #[allow(deprecated)]
@@ -14,7 +22,40 @@ use std::hash::{Hasher, SipHasher};
 
 use byteorder::{BigEndian, ByteOrder};
 
+use compare::Baseline;
+use features::FeatureHasher;
+use hll::HyperLogLog;
+use imp::{Bits, FieldElem31, FieldElem61, FieldElem89, OddU128, OddU32, OddU64};
+use input::Source;
+use json::Value;
+use lsh::LshIndex;
+use minhash::MinHasher;
+use registry::Registry;
+use rng::SplitMix64;
+use sharding::ConsistentRing;
+
+pub mod adversary;
+pub mod affinity;
+pub mod analysis;
+pub mod battery;
+pub mod compare;
+pub mod features;
+pub mod hll;
 pub mod imp;
+pub mod input;
+pub mod json;
+pub mod lsh;
+pub mod minhash;
+pub mod perf;
+pub mod plot;
+pub mod recovery;
+#[cfg(test)]
+mod reference;
+pub mod registry;
+pub mod rng;
+pub mod sharding;
+pub mod table;
+pub mod tsc;
 
 ////////////////////////////////////////
 // Entry Point
@@ -24,50 +65,201 @@ pub mod imp;
 pub enum OutputMode {
     Csv,
     Pretty,
+    Json,
+    JsonLines,
 }
 
 impl OutputMode {
     pub fn is_csv(self) -> bool {
-        match self {
-            OutputMode::Csv => true,
-            _ => false,
-        }
+        matches!(self, OutputMode::Csv)
     }
 
     pub fn is_pretty(self) -> bool {
-        match self {
-            OutputMode::Pretty => true,
-            _ => true,
-        }
+        matches!(self, OutputMode::Pretty)
     }
-}
-
-pub fn main() {
-    // Read input file.
 
-    let mut input_raw = Vec::new();
-    {
-        let mut file = File::open("input.txt").unwrap();
-        file.read_to_end(&mut input_raw).unwrap();
+    pub fn is_json(self) -> bool {
+        matches!(self, OutputMode::Json | OutputMode::JsonLines)
     }
+}
 
+const USAGE: &str =
+    "Usage: cargo run --release -- [-cpjl] [-i PATH | -g KIND] [-n N] [-w N] [-t N] \
+                     [--tsc|--cycles] [--perf] [--list] [--filter GLOB] [--exclude GLOB] \
+                     [--compare PATH [--threshold PCT]] \
+                     [--latency|--sizes LIST] \
+                     [--threads N] 1-14
+       cargo run --release -- plot [CSV_DIR [SVG_DIR]]";
+
+pub fn main() {
     // Parse arguments.
 
     let mut mode = None;
     let mut experiment = 0;
+    let mut timing = Timing::default();
+    let mut source = Source::default();
+    let mut size = input::DEFAULT_SIZE;
+    let mut registry = Registry::default();
+    let mut compare = None;
+    let mut threshold = 5.0;
+    let mut sizes = Vec::new();
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
 
     let mut args = env::args();
 
     let argv0 = args.next().unwrap();
 
-    for arg in args {
+    let mut args = args.peekable();
+    if args.peek().map(|arg| &arg[..]) == Some("plot") {
+        let args = args.skip(1).collect::<Vec<_>>();
+        if args.len() > 2 {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+        let input_dir = args.first().map_or("output", |dir| &dir[..]);
+        let output_dir = args.get(1).map_or("plots", |dir| &dir[..]);
+        match plot::plot_all(input_dir, output_dir) {
+            Ok(ref written) if written.is_empty() => {
+                eprintln!("{}: no experiment CSV files in {}", argv0, input_dir);
+                process::exit(1);
+            }
+            Ok(written) => {
+                for path in written {
+                    println!("{}", path);
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: cannot plot: {}", argv0, err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    while let Some(arg) = args.next() {
         match &arg[..] {
             "-c" => mode = Some(OutputMode::Csv),
             "-p" => mode = Some(OutputMode::Pretty),
+            "-j" => mode = Some(OutputMode::Json),
+            "-l" => mode = Some(OutputMode::JsonLines),
+            "-i" => match args.next() {
+                Some(path) => source = Source::path(&path),
+                None => {
+                    eprintln!("{}: -i expects a path", argv0);
+                    process::exit(2);
+                }
+            },
+            "-g" => match args.next().and_then(|name| Source::generator(&name)) {
+                Some(generator) => source = generator,
+                None => {
+                    eprintln!(
+                        "{}: -g expects one of uniform, sequential, dense, repeat",
+                        argv0
+                    );
+                    process::exit(2);
+                }
+            },
+            "-n" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => size = n,
+                None => {
+                    eprintln!("{}: -n expects a number of bytes", argv0);
+                    process::exit(2);
+                }
+            },
+            "-w" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => timing.warmup = n,
+                None => {
+                    eprintln!("{}: -w expects a number of warmup trials", argv0);
+                    process::exit(2);
+                }
+            },
+            "--tsc" | "--cycles" => {
+                if !tsc::is_available() {
+                    eprintln!("{}: {} needs rdtscp and an invariant TSC", argv0, arg);
+                    process::exit(2);
+                }
+                let cycles = arg == "--cycles";
+                let ticks_per_ns = tsc::calibrate();
+                timing.clock = Clock::Tsc {
+                    ticks_per_ns,
+                    cycles,
+                };
+            }
+            "--perf" => {
+                if !perf::Counters::open().is_available() {
+                    eprintln!("{}: warning: no performance counters available", argv0);
+                }
+                timing.perf = true;
+            }
+            "-t" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => timing.trials = n,
+                _ => {
+                    eprintln!("{}: -t expects a positive number of trials", argv0);
+                    process::exit(2);
+                }
+            },
+            "--list" => registry.list = true,
+            "--filter" | "--exclude" => match args.next() {
+                Some(pattern) => {
+                    if arg == "--filter" {
+                        registry.filters.push(pattern);
+                    } else {
+                        registry.excludes.push(pattern);
+                    }
+                }
+                None => {
+                    eprintln!("{}: {} expects a glob pattern", argv0, arg);
+                    process::exit(2);
+                }
+            },
+            "--compare" => match args.next() {
+                Some(path) => compare = Some(path),
+                None => {
+                    eprintln!("{}: --compare expects a baseline CSV file", argv0);
+                    process::exit(2);
+                }
+            },
+            "--threshold" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n >= 0.0 => threshold = n,
+                _ => {
+                    eprintln!("{}: --threshold expects a non-negative percentage", argv0);
+                    process::exit(2);
+                }
+            },
+            "--latency" => sizes = MESSAGE_SIZES.to_vec(),
+            "--sizes" => match args.next().and_then(|list| parse_sizes(&list)) {
+                Some(list) => sizes = list,
+                None => {
+                    eprintln!(
+                        "{}: --sizes expects a comma-separated list of positive multiples of 8",
+                        argv0
+                    );
+                    process::exit(2);
+                }
+            },
+            "--threads" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => threads = n,
+                _ => {
+                    eprintln!("{}: --threads expects a positive number of threads", argv0);
+                    process::exit(2);
+                }
+            },
             "1" => experiment = 1,
             "2" => experiment = 2,
+            "3" => experiment = 3,
+            "4" => experiment = 4,
+            "5" => experiment = 5,
+            "6" => experiment = 6,
+            "7" => experiment = 7,
+            "8" => experiment = 8,
+            "9" => experiment = 9,
+            "10" => experiment = 10,
+            "11" => experiment = 11,
+            "12" => experiment = 12,
+            "13" => experiment = 13,
+            "14" => experiment = 14,
             _ => {
-                eprintln!("Usage: cargo run --release -- [-cp] 1-2");
+                eprintln!("{}", USAGE);
                 eprintln!("{}: unexpected argument {:?}", argv0, arg);
                 process::exit(2);
             }
@@ -76,30 +268,189 @@ pub fn main() {
 
     // Validate arguments.
 
-    if experiment == 0 {
+    if registry.list {
+        if experiment > 2 {
+            eprintln!("{}: --list only applies to experiments 1-2", argv0);
+            process::exit(2);
+        }
+        mode = Some(OutputMode::Pretty);
+    } else if experiment == 0 {
         mode = None;
+    } else if let Some(ref path) = compare {
+        if experiment > 2 {
+            eprintln!("{}: --compare only applies to experiments 1-2", argv0);
+            process::exit(2);
+        }
+        if !sizes.is_empty() {
+            eprintln!("{}: --compare does not apply to message sizes", argv0);
+            process::exit(2);
+        }
+        let baseline = match Baseline::load(path, threshold / 100.0) {
+            Ok(baseline) => baseline,
+            Err(err) => {
+                eprintln!("{}: cannot read baseline {}: {}", argv0, path, err);
+                process::exit(1);
+            }
+        };
+        if baseline.column() != timing.clock.column() {
+            eprintln!(
+                "{}: baseline {} is in {}, but this run measures {}",
+                argv0,
+                path,
+                baseline.column(),
+                timing.clock.column()
+            );
+            process::exit(2);
+        }
+        registry.baseline = Some(baseline);
+        mode = Some(OutputMode::Pretty);
     }
 
     let mode = match mode {
         Some(mode) => mode,
         None => {
-            eprintln!("Usage: cargo run --release -- [-cp] 1-2");
+            eprintln!("{}", USAGE);
             eprintln!("Options:");
             eprintln!("  -c        Output as CSV.");
             eprintln!("  -p        Output as human-readable text.");
+            eprintln!("  -j        Output as one JSON document.");
+            eprintln!("  -l        Output as JSON lines, one object per result.");
+            eprintln!("  -i PATH   Read input from PATH, or stdin for - (default input.txt).");
+            eprintln!("  -g KIND   Generate input: uniform, sequential, dense or repeat.");
+            eprintln!("  -n N      Bytes of generated input (default 1048576).");
+            eprintln!("  -w N      Untimed warmup trials per sample (default 2).");
+            eprintln!("  -t N      Timed trials per sample (default 10).");
+            eprintln!("  --tsc     Time with the time stamp counter, reported in ns.");
+            eprintln!("  --cycles  Time with the time stamp counter, reported in cycles.");
+            eprintln!("  --perf    Add hardware event counts per value (Linux only).");
+            eprintln!("  --list    List the benchmark cases of experiments 1-2 instead.");
+            eprintln!("  --filter GLOB");
+            eprintln!("            Only run benchmark cases matching GLOB, e.g. 'poly/*'.");
+            eprintln!("  --exclude GLOB");
+            eprintln!("            Skip benchmark cases matching GLOB.");
+            eprintln!("  --compare PATH");
+            eprintln!("            Compare against a previous CSV result of experiment 1 or 2");
+            eprintln!("            and exit with status 3 if any case regressed.");
+            eprintln!("  --threshold PCT");
+            eprintln!("            Relative change that counts as significant (default 5).");
+            eprintln!("  --latency Time experiment 2 as throughput and latency per message");
+            eprintln!("            size, from 8 B to 1 MiB.");
+            eprintln!("  --sizes LIST");
+            eprintln!("            Like --latency with the given message sizes in bytes, e.g.");
+            eprintln!("            8,64,4096.");
+            eprintln!("  --threads N");
+            eprintln!("            Most threads of experiment 8 (default: available cores).");
             eprintln!("Arguments:");
-            eprintln!("  1-2       Select the experiment to perform.");
+            eprintln!("  1-14      Select the experiment to perform.");
+            eprintln!("Subcommands:");
+            eprintln!("  plot      Render CSV_DIR/experiment{{1,2}}.csv (default output) as SVG");
+            eprintln!("            charts in SVG_DIR (default plots).");
             process::exit(2);
         }
     };
 
+    // Read input.
+
+    let input_raw = match source.read(size) {
+        Ok(raw) => raw,
+        Err(err) => {
+            eprintln!("{}: cannot read input {}: {}", argv0, source, err);
+            process::exit(1);
+        }
+    };
+
+    // Experiments that hash the input need a whole word of up to 16 bytes (8 for
+    // experiment 8); the others, and listing the cases, ignore it.
+    let min_input = match experiment {
+        _ if registry.list => 0,
+        0..=7 | 10 => 16,
+        8 => 8,
+        _ => 0,
+    };
+    if input_raw.len() < min_input {
+        eprintln!(
+            "{}: input {} has fewer than {} bytes",
+            argv0, source, min_input
+        );
+        process::exit(2);
+    }
+
     // Perform experiment.
 
+    if mode.is_json() {
+        let size = input_raw.len() as f64;
+        let source = source.to_string();
+        let cpu = cpu_model();
+        json::begin(
+            mode,
+            &[
+                ("input", Value::Str(&source)),
+                ("input_bytes", Value::Num(size)),
+                ("cpu", Value::Str(&cpu)),
+                ("rustc", Value::Str(env!("HASHING_RUSTC_VERSION"))),
+                ("commit", Value::Str(env!("HASHING_GIT_COMMIT"))),
+                ("warmup", Value::Num(f64::from(timing.warmup))),
+                ("trials", Value::Num(f64::from(timing.trials))),
+            ],
+        );
+    }
+
     match experiment {
-        1 => experiment_1(mode, &input_raw),
-        2 => experiment_2(mode, &input_raw),
+        0 => {
+            experiment_1(mode, timing, &registry, &input_raw);
+            experiment_2(mode, timing, &registry, &sizes, &input_raw);
+        }
+        1 => experiment_1(mode, timing, &registry, &input_raw),
+        2 => experiment_2(mode, timing, &registry, &sizes, &input_raw),
+        3 => experiment_3(mode, &input_raw),
+        4 => experiment_4(mode, &input_raw),
+        5 => experiment_5(mode, &input_raw),
+        6 => experiment_6(mode, &input_raw),
+        7 => experiment_7(mode, &input_raw),
+        8 => experiment_8(mode, timing, threads, &input_raw),
+        9 => experiment_9(mode),
+        10 => experiment_10(mode, &input_raw),
+        11 => experiment_11(mode),
+        12 => experiment_12(mode),
+        13 => experiment_13(mode),
+        14 => experiment_14(mode),
         _ => unreachable!(),
     }
+
+    if mode.is_json() {
+        json::end(mode);
+    }
+
+    if let Some(ref baseline) = registry.baseline {
+        if !baseline.finish() {
+            process::exit(3);
+        }
+    }
+}
+
+// Message sizes of --latency in bytes, 8 B to 1 MiB.
+const MESSAGE_SIZES: [usize; 7] = [8, 64, 512, 4096, 32768, 262144, 1048576];
+
+// Comma-separated message sizes; the experiment 2 families read 64-bit words.
+fn parse_sizes(list: &str) -> Option<Vec<usize>> {
+    list.split(',')
+        .map(|size| match size.trim().parse::<usize>() {
+            Ok(size) if size > 0 && size & 7 == 0 => Some(size),
+            _ => None,
+        })
+        .collect()
+}
+
+// The "model name" line of /proc/cpuinfo, where available.
+fn cpu_model() -> String {
+    let mut cpuinfo = String::new();
+    let _ = File::open("/proc/cpuinfo").and_then(|mut file| file.read_to_string(&mut cpuinfo));
+    cpuinfo
+        .lines()
+        .find(|line| line.starts_with("model name"))
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, model)| model.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
 }
 
 ////////////////////////////////////////
@@ -120,30 +471,219 @@ where
     secs * 1e9 + nanos
 }
 
-pub fn time_nanos<F>(reps: u32, mut func: F) -> f64
+// Time of one call in the unit of the clock.
+pub fn time_one<F>(clock: Clock, func: F) -> f64
+where
+    F: FnMut(),
+{
+    match clock {
+        Clock::Instant => time_nanos_one(func),
+        Clock::Tsc {
+            ticks_per_ns,
+            cycles,
+        } => {
+            let ticks = tsc::time_ticks_one(func);
+            if cycles {
+                ticks
+            } else {
+                ticks / ticks_per_ns
+            }
+        }
+    }
+}
+
+pub fn time_reps<F>(clock: Clock, reps: u32, mut func: F) -> f64
 where
     F: FnMut(),
 {
-    let nanos = time_nanos_one(|| {
+    let time = time_one(clock, || {
         for _ in 0..reps {
             func();
         }
     });
 
-    nanos / (reps as f64)
+    time / (reps as f64)
 }
 
-pub fn time_nanos_slice<T, F>(reps: u32, input: &[T], mut func: F) -> f64
+pub fn time_reps_slice<T, F>(clock: Clock, reps: u32, input: &[T], mut func: F) -> f64
 where
     F: FnMut(&T),
 {
-    let nanos = time_nanos(reps, || {
+    let time = time_reps(clock, reps, || {
         for value in input {
             func(value);
         }
     });
 
-    nanos / (input.len() as f64)
+    time / (input.len() as f64)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clock {
+    Instant,
+    // Time stamp counter, reported in ticks if cycles is set and in ns otherwise.
+    Tsc { ticks_per_ns: f64, cycles: bool },
+}
+
+impl Clock {
+    pub fn unit(self) -> &'static str {
+        match self {
+            Clock::Tsc { cycles: true, .. } => "cycles",
+            _ => "ns",
+        }
+    }
+
+    pub fn rate_unit(self) -> &'static str {
+        match self {
+            Clock::Tsc { cycles: true, .. } => "bytes/cycle",
+            _ => "GB/s",
+        }
+    }
+
+    // Name of the CSV column holding a throughput.
+    pub fn rate_column(self) -> &'static str {
+        match self {
+            Clock::Tsc { cycles: true, .. } => "bytes_per_cycle",
+            _ => "gb_per_s",
+        }
+    }
+
+    // Name of the CSV column holding the median.
+    pub fn column(self) -> &'static str {
+        match self {
+            Clock::Tsc { cycles: true, .. } => "cycles",
+            _ => "nanos",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Timing {
+    pub clock: Clock,
+    pub warmup: u32,
+    pub trials: u32,
+    pub perf: bool,
+}
+
+impl Timing {
+    pub fn csv_header(&self) -> String {
+        format!(
+            "{},mean,stddev,min,ci_low,ci_high{}",
+            self.clock.column(),
+            perf::csv_header(self.perf)
+        )
+    }
+
+    // Number of times a sample runs its input, warmup included.
+    pub fn runs(&self) -> f64 {
+        f64::from(self.warmup + self.trials)
+    }
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            clock: Clock::Instant,
+            warmup: 2,
+            trials: 10,
+            perf: false,
+        }
+    }
+}
+
+// Summary of the per-trial timings of one sample; ci95 is the 95% confidence
+// interval of the mean.
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub median: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub ci95: (f64, f64),
+}
+
+impl Stats {
+    pub fn from_samples(samples: &mut [f64]) -> Self {
+        assert!(!samples.is_empty());
+        samples.sort_by(f64::total_cmp);
+
+        let n = samples.len();
+        let median = if n % 2 == 1 {
+            samples[n / 2]
+        } else {
+            0.5 * (samples[n / 2 - 1] + samples[n / 2])
+        };
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let stddev = if n > 1 {
+            let ss = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>();
+            (ss / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let half = student_t95(n) * stddev / (n as f64).sqrt();
+
+        Self {
+            median,
+            mean,
+            stddev,
+            min: samples[0],
+            ci95: (mean - half, mean + half),
+        }
+    }
+
+    pub fn csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{}",
+            self.median, self.mean, self.stddev, self.min, self.ci95.0, self.ci95.1
+        )
+    }
+
+    pub fn json(&self) -> [(&'static str, Value<'static>); 6] {
+        [
+            ("median", Value::Num(self.median)),
+            ("mean", Value::Num(self.mean)),
+            ("stddev", Value::Num(self.stddev)),
+            ("min", Value::Num(self.min)),
+            ("ci_low", Value::Num(self.ci95.0)),
+            ("ci_high", Value::Num(self.ci95.1)),
+        ]
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.6} (mean: {:.6}, sd: {:.6}, min: {:.6}, 95% CI: [{:.6}, {:.6}])",
+            self.median, self.mean, self.stddev, self.min, self.ci95.0, self.ci95.1
+        )
+    }
+}
+
+// Two-sided 95% quantile of Student's t distribution with n - 1 degrees of freedom.
+fn student_t95(n: usize) -> f64 {
+    const T: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match n {
+        0 | 1 => 0.0,
+        n if n <= 31 => T[n - 2],
+        _ => 1.960,
+    }
+}
+
+// Runs func for the untimed warmup trials, then summarizes the timed trials.
+pub fn time_trials<F>(timing: Timing, mut func: F) -> Stats
+where
+    F: FnMut() -> f64,
+{
+    for _ in 0..timing.warmup {
+        func();
+    }
+    let mut samples = (0..timing.trials).map(|_| func()).collect::<Vec<_>>();
+    Stats::from_samples(&mut samples)
 }
 
 ////////////////////////////////////////
@@ -152,6 +692,8 @@ where
 
 pub struct Spec1<'a, T: 'a> {
     mode: OutputMode,
+    timing: Timing,
+    registry: &'a Registry,
     family: (&'a str, u32, bool),
     input: (u32, &'a [T]),
 }
@@ -164,22 +706,68 @@ impl<'a, T: 'a> Spec1<'a, T> {
         let (scheme, bits, is_128) = self.family;
         let (reps, input) = self.input;
 
+        let name = format!("{}/{}", scheme, bits);
+        if !self.registry.visit(&name) {
+            return;
+        }
+
+        // Inputs shorter than one message leave nothing to time.
+        if input.is_empty() {
+            return;
+        }
+
         let input = test::black_box(input);
 
         let mut state = 0;
-        let nanos = time_nanos_slice(reps, input, |value| state ^= func(value));
+        let (stats, counts) = perf::count_if(self.timing.perf, || {
+            time_trials(self.timing, || {
+                time_reps_slice(self.timing.clock, reps, input, |value| state ^= func(value))
+            })
+        });
         let _ = test::black_box(state);
 
+        if let Some(ref baseline) = self.registry.baseline {
+            baseline.check(&name, self.timing.clock.unit(), &stats);
+            return;
+        }
+
+        let values = self.timing.runs() * f64::from(reps) * (input.len() as f64);
+
         match self.mode {
             OutputMode::Pretty => {
                 println!(
-                    "Scheme: {}, input bit-length: {}, 128-bit: {}; ns/value: {:.6}",
-                    scheme, bits, is_128, nanos
+                    "Scheme: {}, input bit-length: {}, 128-bit: {}; {}/value: {}{}",
+                    scheme,
+                    bits,
+                    is_128,
+                    self.timing.clock.unit(),
+                    stats,
+                    perf::pretty(&counts, values)
                 );
             }
             OutputMode::Csv => {
                 let is_128 = if is_128 { "TRUE" } else { "FALSE" };
-                println!("{},{},{},{}", scheme, bits, is_128, nanos);
+                println!(
+                    "{},{},{},{}{}",
+                    scheme,
+                    bits,
+                    is_128,
+                    stats.csv(),
+                    perf::csv(&counts, values)
+                );
+            }
+            OutputMode::Json | OutputMode::JsonLines => {
+                let mut fields = vec![
+                    ("experiment", Value::Num(1.0)),
+                    ("scheme", Value::Str(scheme)),
+                    ("bits", Value::Num(f64::from(bits))),
+                    ("is128", Value::Bool(is_128)),
+                    ("key_seed", Value::Null),
+                    ("unit", Value::Str(self.timing.clock.unit())),
+                ];
+                fields.extend_from_slice(&stats.json());
+                fields.extend_from_slice(&perf::json(&counts, values));
+                json::row(self.mode, &fields);
             }
         }
     }
@@ -209,7 +797,7 @@ where
     vec.iter().map(|vec| &vec[..]).collect()
 }
 
-pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
+pub fn experiment_1(mode: OutputMode, timing: Timing, registry: &Registry, input_raw: &[u8]) {
     let input_raw = &input_raw[..input_raw.len() & !15];
 
     let mut input_32 = vec![0; input_raw.len() / 4];
@@ -232,10 +820,10 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
         .map(|&x| x & ((1 << 60) - 1))
         .collect::<Vec<_>>();
 
-    let reps = 100;
+    let reps = 10;
 
-    if mode.is_csv() {
-        println!("scheme,bits,is128,nanos");
+    if mode.is_csv() && !registry.list {
+        println!("scheme,bits,is128,{}", timing.csv_header());
     }
 
     // Multiply-Shift
@@ -243,59 +831,74 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("shift", 32, false),
             input: (reps, &input_32[..]),
         };
 
-        let a = test::black_box(0x3bca40c7);
+        let l = Bits::new(20).unwrap();
+        let a = test::black_box(OddU32::new(0x3bca40c7).unwrap());
 
-        spec.sample(|&x| imp::shift_u32(20, a, x));
+        spec.sample(|&x| imp::shift_u32(l, a, x));
     }
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("shift", 64, false),
             input: (reps, &input_64[..]),
         };
 
-        let a = test::black_box(0xa570f20b9bd5adfb);
+        let l = Bits::new(20).unwrap();
+        let a = test::black_box(OddU64::new(0xa570f20b9bd5adfb).unwrap());
 
-        spec.sample(|&x| imp::shift_u64(20, a, x) as u32);
+        spec.sample(|&x| imp::shift_u64(l, a, x) as u32);
     }
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("shift", 128, true),
             input: (reps, &input_128[..]),
         };
 
-        let a = test::black_box(0x2cb56e50f9538749b4a1648382ba0d59);
+        let l = Bits::new(20).unwrap();
+        let a = test::black_box(OddU128::new(0x2cb56e50f9538749b4a1648382ba0d59).unwrap());
 
-        spec.sample(|&x| imp::shift_u128_128(20, a, x) as u32);
+        spec.sample(|&x| imp::shift_u128_128(l, a, x) as u32);
     }
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("shift-strong", 32, false),
             input: (reps, &input_32[..]),
         };
 
+        let l = Bits::new(20).unwrap();
         let a = test::black_box(0x9cb37f1a);
         let b = test::black_box(0x2d8b1736);
 
-        spec.sample(|&x| imp::shift_strong_u32(20, a, b, x) as u32);
+        spec.sample(|&x| imp::shift_strong_u32(l, a, b, x) as u32);
     }
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("shift-strong", 64, true),
             input: (reps, &input_64[..]),
         };
 
+        let l = Bits::new(20).unwrap();
         let a = test::black_box(0x6865db19e3d1b464);
         let b = test::black_box(0x583bc159d427a991);
 
-        spec.sample(|&x| imp::shift_strong_u64_128(20, a, b, x) as u32);
+        spec.sample(|&x| imp::shift_strong_u64_128(l, a, b, x) as u32);
     }
 
     // Multiply-Mod-Prime
@@ -303,64 +906,85 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("mmp", 30, false),
             input: (reps, &input_30[..]),
         };
 
-        let a = test::black_box(0x40ed8147);
-        let b = test::black_box(0x64b07a26);
+        let l = Bits::new(20).unwrap();
+        let a = test::black_box(FieldElem31::new(0x40ed8147).unwrap());
+        let b = test::black_box(FieldElem31::new(0x64b07a26).unwrap());
 
-        spec.sample(|&x| imp::mmp_p31_u30(20, a, b, x));
+        spec.sample(|&x| imp::mmp_p31_u30(l, a, b, x));
     }
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("mmp-triple", 64, false),
             input: (reps, &input_64[..]),
         };
 
-        let a = test::black_box([0x68dc5b2d, 0x29ad0bce, 0x278a331a]);
-        let b = test::black_box([0x3e4f5b23, 0x2e47ea16, 0x3c665bad]);
+        let l = Bits::new(20).unwrap();
+        let a = [0x68dc5b2d, 0x29ad0bce, 0x278a331a].map(|a| FieldElem31::new(a).unwrap());
+        let b = [0x3e4f5b23, 0x2e47ea16, 0x3c665bad].map(|b| FieldElem31::new(b).unwrap());
+        let (a, b) = (test::black_box(a), test::black_box(b));
 
-        spec.sample(|&x| imp::mmp_p31_u64(20, a, b, x));
+        spec.sample(|&x| imp::mmp_p31_u64(l, a, b, x));
     }
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("mmp", 60, true),
             input: (reps, &input_60[..]),
         };
 
-        let a = test::black_box(0x02f52fcd0b6474c3);
-        let b = test::black_box(0x0cb11e6766f6e421);
+        let l = Bits::new(20).unwrap();
+        let a = test::black_box(FieldElem61::new(0x02f52fcd0b6474c3).unwrap());
+        let b = test::black_box(FieldElem61::new(0x0cb11e6766f6e421).unwrap());
 
-        spec.sample(|&x| imp::mmp_p61_u60_128(20, a, b, x) as u32);
+        spec.sample(|&x| imp::mmp_p61_u60_128(l, a, b, x) as u32);
     }
     {
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("mmp", 64, false),
             input: (reps, &input_64[..]),
         };
 
-        let a = test::black_box([0xc543be39, 0xf663c8a4, 0x017193ad]);
-        let b = test::black_box([0x180375ec, 0xd6fbb57d, 0x0010c0af]);
+        let l = Bits::new(20).unwrap();
+        let a = test::black_box(FieldElem89::new([0xc543be39, 0xf663c8a4, 0x017193ad]).unwrap());
+        let b = test::black_box(FieldElem89::new([0x180375ec, 0xd6fbb57d, 0x0010c0af]).unwrap());
 
-        spec.sample(|&x| imp::mmp_p89_u64(20, a, b, x) as u32);
+        spec.sample(|&x| imp::mmp_p89_u64(l, a, b, x) as u32);
     }
 
     // String Hashing
 
     for n in 1..=64 {
+        // Skipped cases do not pay for preparing their input chunks.
+        if !registry.is_selected(&format!("vector-shift/{}", 32 * n)) {
+            continue;
+        }
+
         let mut vec = Vec::new();
         let input = prepare_input_chunked(input_raw, 4, n, &mut vec, BigEndian::read_u32_into);
 
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("vector-shift", (32 * n) as u32, false),
             input: (reps, &input[..]),
         };
 
+        let l = Bits::new(20).unwrap();
         let a = test::black_box([
             0x63b92c3f6df33488,
             0xa2207bc53adff964,
@@ -434,20 +1058,27 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
             for &x in chunk {
                 h.write_u32(x);
             }
-            h.finish(20)
+            h.finish(l)
         });
     }
 
     for n in 1..=32 {
+        if !registry.is_selected(&format!("pair-shift/{}", 64 * n)) {
+            continue;
+        }
+
         let mut vec = Vec::new();
         let input = prepare_input_chunked(input_raw, 8, n, &mut vec, BigEndian::read_u64_into);
 
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("pair-shift", (64 * n) as u32, false),
             input: (reps, &input[..]),
         };
 
+        let l = Bits::new(20).unwrap();
         let a = test::black_box([
             0xa32b511bb9419925,
             0x468967dfa5b55d7c,
@@ -521,66 +1152,87 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
             for &x in chunk {
                 h.write_u64(x);
             }
-            h.finish(20) as u32
+            h.finish(l) as u32
         });
     }
 
     for n in 1..=48 {
+        if !registry.is_selected(&format!("poly/{}", 64 * n)) {
+            continue;
+        }
+
         let mut vec = Vec::new();
         let input = prepare_input_chunked(input_raw, 8, n, &mut vec, BigEndian::read_u64_into);
 
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("poly", (64 * n) as u32, false),
             input: (reps, &input[..]),
         };
 
-        let a = test::black_box([0x0b9ba445, 0x417af2f0, 0x0057491f]);
-        let b = test::black_box([0x7955e3d0, 0x9bc12da3, 0x00199f87]);
-        let c = test::black_box([0xaf04903c, 0xa17b4a10, 0x01db4b2a]);
+        let l = Bits::new(20).unwrap();
+        let a = test::black_box(FieldElem89::new([0x0b9ba445, 0x417af2f0, 0x0057491f]).unwrap());
+        let b = test::black_box(FieldElem89::new([0x7955e3d0, 0x9bc12da3, 0x00199f87]).unwrap());
+        let c = test::black_box(FieldElem89::new([0xaf04903c, 0xa17b4a10, 0x01db4b2a]).unwrap());
 
         spec.sample(|&chunk| {
             let mut h = imp::PolyU64::new(a, b, c);
             for &x in chunk {
                 h.write_u64(x);
             }
-            h.finish(20) as u32
+            h.finish(l) as u32
         });
     }
 
     for n in 1..=48 {
+        if !registry.is_selected(&format!("poly-shift-triple/{}", 64 * n)) {
+            continue;
+        }
+
         let mut vec = Vec::new();
         let input = prepare_input_chunked(input_raw, 8, n, &mut vec, BigEndian::read_u64_into);
 
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("poly-shift-triple", (64 * n) as u32, false),
             input: (reps, &input[..]),
         };
 
+        let l = Bits::new(20).unwrap();
         let a = test::black_box([0x8ef01a402b94d1c0, 0xb51e6c8268b54d1e, 0x03de6480bede297c]);
         let b = test::black_box([0xd50d4b3992ece1d9, 0x03a9c4958c1da245, 0x8b24cd3ab504d6dd]);
-        let c = test::black_box([0xb96bffb, 0xf3a4f6ed, 0x00304d5]);
+        let c = test::black_box(FieldElem89::new([0xb96bffb, 0xf3a4f6ed, 0x00304d5]).unwrap());
 
         spec.sample(|&chunk| {
             let mut h = imp::PolyShiftU64::new(a, b, c);
             for &x in chunk {
                 h.write_u64(x);
             }
-            h.finish(20) as u32
+            h.finish(l) as u32
         });
     }
 
     for n in 1..=48 {
+        if !registry.is_selected(&format!("preproc-poly/{}", 64 * n)) {
+            continue;
+        }
+
         let mut vec = Vec::new();
         let input = prepare_input_chunked(input_raw, 8, n, &mut vec, BigEndian::read_u64_into);
 
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("preproc-poly", (64 * n) as u32, false),
             input: (reps, &input[..]),
         };
 
+        let l = Bits::new(20).unwrap();
         let prep1 = test::black_box([
             0xd4f3ea9a2272d2c9,
             0xdc3760cf448d30de,
@@ -715,21 +1367,25 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
             0x7406c10cada8c543,
             0x87565863da322aac,
         ]);
-        let a = test::black_box([0xec9f5086, 0xb59700c9, 0x00b9156b]);
-        let b = test::black_box([0xf2dcaf8a, 0x2fbd1937, 0x019dfd97]);
-        let c = test::black_box([0x71976d5e, 0xe5ee27a3, 0x00528ef6]);
+        let a = test::black_box(FieldElem89::new([0xec9f5086, 0xb59700c9, 0x00b9156b]).unwrap());
+        let b = test::black_box(FieldElem89::new([0xf2dcaf8a, 0x2fbd1937, 0x019dfd97]).unwrap());
+        let c = test::black_box(FieldElem89::new([0x71976d5e, 0xe5ee27a3, 0x00528ef6]).unwrap());
 
         spec.sample(|&chunk| {
             let mut h = imp::PreprocPolyU64D32::new(prep1, prep2, a, b, c);
             for &x in chunk {
                 h.write_u64(x);
             }
-            h.finish(20) as u32
+            h.finish(l) as u32
         });
     }
 
     #[allow(deprecated)]
     for n in 1..=384 {
+        if !registry.is_selected(&format!("siphash/{}", 8 * n)) {
+            continue;
+        }
+
         let mut vec = Vec::new();
         let input = prepare_input_chunked(input_raw, 1, n, &mut vec, |src, dst| {
             dst.copy_from_slice(src)
@@ -737,6 +1393,8 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
 
         let spec = Spec1 {
             mode,
+            timing,
+            registry,
             family: ("siphash", (8 * n) as u32, false),
             input: (reps, &input[..]),
         };
@@ -756,102 +1414,266 @@ pub fn experiment_1(mode: OutputMode, input_raw: &[u8]) {
 // Experiment 2
 ////////////////////////////////////////
 
+// Description: With no message sizes, hashes the whole input as one message and
+// reports time per byte. With message sizes, hashes the input as consecutive
+// messages of each size and reports throughput and latency. Throughput hashes the
+// messages independently; latency hashes one message repeatedly with each hash fed
+// back into the message, so every hash waits for the one before it.
+// Parameters: config is (mode, timing, input length in bytes, message sizes in
+// bytes).
+
 pub struct Spec2<'a, T: 'a> {
-    config: (OutputMode, usize),
+    config: (OutputMode, Timing, usize, &'a [usize]),
+    registry: &'a Registry,
     family: &'a str,
     input: (u32, &'a [T]),
 }
 
-impl<'a, T: 'a> Spec2<'a, T> {
+impl<'a, T: 'a> Spec2<'a, T>
+where
+    T: Copy + BitXor<Output = T> + From<u8>,
+{
     pub fn sample<F>(&self, mut func: F)
     where
         F: FnMut(&[T]) -> u32,
     {
-        let (mode, input_len) = self.config;
+        let (mode, timing, input_len, sizes) = self.config;
         let family = self.family;
         let (reps, input) = self.input;
 
+        if !self.registry.visit(family) {
+            return;
+        }
+
         let input = test::black_box(input);
 
+        if !sizes.is_empty() {
+            for &size in sizes {
+                self.sample_size(size, &mut func);
+            }
+            return;
+        }
+
+        let mut state = 0;
+        let (stats, counts) = perf::count_if(timing.perf, || {
+            time_trials(timing, || {
+                time_reps(timing.clock, reps, || state ^= func(input)) / (input_len as f64)
+            })
+        });
+        let _ = test::black_box(state);
+
+        if let Some(ref baseline) = self.registry.baseline {
+            baseline.check(family, timing.clock.unit(), &stats);
+            return;
+        }
+
+        let values = timing.runs() * f64::from(reps) * (input_len as f64);
+
+        match mode {
+            OutputMode::Pretty => {
+                println!(
+                    "Family: {}; {}/value: {}{}",
+                    family,
+                    timing.clock.unit(),
+                    stats,
+                    perf::pretty(&counts, values)
+                );
+            }
+            OutputMode::Csv => {
+                println!("{},{}{}", family, stats.csv(), perf::csv(&counts, values));
+            }
+            OutputMode::Json | OutputMode::JsonLines => {
+                let mut fields = vec![
+                    ("experiment", Value::Num(2.0)),
+                    ("family", Value::Str(family)),
+                    ("key_seed", Value::Null),
+                    ("unit", Value::Str(timing.clock.unit())),
+                ];
+                fields.extend_from_slice(&stats.json());
+                fields.extend_from_slice(&perf::json(&counts, values));
+                json::row(mode, &fields);
+            }
+        }
+    }
+
+    fn sample_size<F>(&self, size: usize, func: &mut F)
+    where
+        F: FnMut(&[T]) -> u32,
+    {
+        let (mode, timing, input_len, _) = self.config;
+        let family = self.family;
+        let (reps, input) = self.input;
+
+        let len = size / (input_len / input.len());
+        let messages = input.len() / len;
+        let input = &input[..messages * len];
+
         let mut state = 0;
-        let nanos = time_nanos(reps, || state ^= func(input));
+        let throughput = time_trials(timing, || {
+            time_reps(timing.clock, reps, || {
+                for message in input.chunks(len) {
+                    state ^= func(message);
+                }
+            }) / (messages * size) as f64
+        });
         let _ = test::black_box(state);
 
-        let nanos = nanos / (input_len as f64);
+        let mut message = input[..len].to_vec();
+        let (latency, counts) = perf::count_if(timing.perf, || {
+            time_trials(timing, || {
+                time_reps(timing.clock, reps, || {
+                    for _ in 0..messages {
+                        let hash = func(&message);
+                        message[0] = message[0] ^ T::from(hash as u8);
+                    }
+                }) / (messages as f64)
+            })
+        });
+        let _ = test::black_box(&message);
+
+        // Bytes per time unit from time per byte, i.e. GB/s when timed in ns.
+        let rate = 1.0 / throughput.median;
+        let values = timing.runs() * f64::from(reps) * (messages as f64);
 
         match mode {
             OutputMode::Pretty => {
-                println!("Family: {}; ns/value: {:.6}", family, nanos);
+                println!(
+                    "Family: {}, message bytes: {}; throughput: {:.3} {}; {}/message: {}{}",
+                    family,
+                    size,
+                    rate,
+                    timing.clock.rate_unit(),
+                    timing.clock.unit(),
+                    latency,
+                    perf::pretty(&counts, values)
+                );
             }
             OutputMode::Csv => {
-                println!("{},{}", family, nanos);
+                println!(
+                    "{},{},{},{}{}",
+                    family,
+                    size,
+                    rate,
+                    latency.csv(),
+                    perf::csv(&counts, values)
+                );
+            }
+            OutputMode::Json | OutputMode::JsonLines => {
+                let mut fields = vec![
+                    ("experiment", Value::Num(2.0)),
+                    ("family", Value::Str(family)),
+                    ("bytes", Value::Num(size as f64)),
+                    ("key_seed", Value::Null),
+                    ("unit", Value::Str(timing.clock.unit())),
+                    (timing.clock.rate_column(), Value::Num(rate)),
+                ];
+                fields.extend_from_slice(&latency.json());
+                fields.extend_from_slice(&perf::json(&counts, values));
+                json::row(mode, &fields);
             }
         }
     }
 }
 
-pub fn experiment_2(mode: OutputMode, input_raw: &[u8]) {
+pub fn experiment_2(
+    mode: OutputMode,
+    timing: Timing,
+    registry: &Registry,
+    sizes: &[usize],
+    input_raw: &[u8],
+) {
     let input_8 = &input_raw[..input_raw.len() & !7];
 
+    let sizes = sizes
+        .iter()
+        .cloned()
+        .filter(|&size| {
+            if size > input_8.len() {
+                eprintln!(
+                    "Skipping message size {}: input has only {} bytes",
+                    size,
+                    input_8.len()
+                );
+            }
+            size <= input_8.len()
+        })
+        .collect::<Vec<_>>();
+
     let mut input_64 = vec![0; input_8.len() / 8];
 
     BigEndian::read_u64_into(&input_8, &mut input_64[..]);
 
     let input_64 = test::black_box(&input_64[..]);
 
-    let reps = 400;
+    let reps = 40;
 
-    let config = (mode, input_8.len());
+    let config = (mode, timing, input_8.len(), &sizes[..]);
 
-    if mode.is_csv() {
-        println!("family,nanos");
+    if mode.is_csv() && !registry.list {
+        if sizes.is_empty() {
+            println!("family,{}", timing.csv_header());
+        } else {
+            println!(
+                "family,bytes,{},{}",
+                timing.clock.rate_column(),
+                timing.csv_header()
+            );
+        }
     }
 
     {
         let spec = Spec2 {
             config,
+            registry,
             family: "poly",
             input: (reps, input_64),
         };
 
-        let a = test::black_box([0x62b2da6d, 0x8826958f, 0x0ec048cd]);
-        let b = test::black_box([0x9f7fe744, 0x94dddebf, 0x2b0d2821]);
-        let c = test::black_box([0x02f6a761, 0xa607ade8, 0x27f45a1d]);
+        let l = Bits::new(20).unwrap();
+        // These keys were drawn as 96 bits; reduced mod p they give the same hash.
+        let a = test::black_box(FieldElem89::reduce([0x62b2da6d, 0x8826958f, 0x0ec048cd]));
+        let b = test::black_box(FieldElem89::reduce([0x9f7fe744, 0x94dddebf, 0x2b0d2821]));
+        let c = test::black_box(FieldElem89::reduce([0x02f6a761, 0xa607ade8, 0x27f45a1d]));
 
         spec.sample(|input| {
             let mut h = imp::PolyU64::new(a, b, c);
             for &x in input {
                 h.write_u64(x);
             }
-            h.finish(20) as u32
+            h.finish(l) as u32
         });
     }
     {
         let spec = Spec2 {
             config,
+            registry,
             family: "poly-shift-triple",
             input: (reps, input_64),
         };
 
+        let l = Bits::new(20).unwrap();
         let a = test::black_box([0x793d61ae7fdd32ab, 0x6012b7e4dc15941d, 0xc8572426ef9b5203]);
         let b = test::black_box([0x7b55dd4f434ac74a, 0x116034079f9a5ee8, 0xe55a53261f00ed6d]);
-        let c = test::black_box([0xb1cae7e6, 0x70417c04, 0xf909b86d]);
+        // Reduced mod p, as for poly.
+        let c = test::black_box(FieldElem89::reduce([0xb1cae7e6, 0x70417c04, 0xf909b86d]));
 
         spec.sample(|input| {
             let mut h = imp::PolyShiftU64::new(a, b, c);
             for &x in input {
                 h.write_u64(x);
             }
-            h.finish(20)
+            h.finish(l)
         });
     }
     {
         let spec = Spec2 {
             config,
+            registry,
             family: "preproc-poly",
             input: (reps, input_64),
         };
 
+        let l = Bits::new(20).unwrap();
         let prep1 = test::black_box([
             0xb2711dd1f64b11f8,
             0xa14d82ab5a17a6a7,
@@ -986,16 +1808,17 @@ pub fn experiment_2(mode: OutputMode, input_raw: &[u8]) {
             0x05a73d52342e4c47,
             0xcc8a6557eda0d91f,
         ]);
-        let a = test::black_box([0x640a3992, 0xa9aec943, 0x061d4e0c]);
-        let b = test::black_box([0x4ff935a2, 0x0e0c9fcb, 0x1d3fb360]);
-        let c = test::black_box([0xb6aaea65, 0x7f9eefc7, 0x320577f3]);
+        // Reduced mod p, as for poly.
+        let a = test::black_box(FieldElem89::reduce([0x640a3992, 0xa9aec943, 0x061d4e0c]));
+        let b = test::black_box(FieldElem89::reduce([0x4ff935a2, 0x0e0c9fcb, 0x1d3fb360]));
+        let c = test::black_box(FieldElem89::reduce([0xb6aaea65, 0x7f9eefc7, 0x320577f3]));
 
         spec.sample(|input| {
             let mut h = imp::PreprocPolyU64D32::new(prep1, prep2, a, b, c);
             for &x in input {
                 h.write_u64(x);
             }
-            h.finish(20) as u32
+            h.finish(l) as u32
         });
     }
 
@@ -1005,6 +1828,7 @@ pub fn experiment_2(mode: OutputMode, input_raw: &[u8]) {
     {
         let spec = Spec2 {
             config,
+            registry,
             family: "siphash",
             input: (reps, input_8),
         };
@@ -1019,3 +1843,2569 @@ pub fn experiment_2(mode: OutputMode, input_raw: &[u8]) {
         });
     }
 }
+
+////////////////////////////////////////
+// Experiment 3
+////////////////////////////////////////
+
+pub struct Spec3<'a> {
+    config: (OutputMode, usize),
+    family: &'a str,
+    input: (u32, &'a [u64]),
+}
+
+impl<'a> Spec3<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: FnMut(u64) -> u64,
+    {
+        let (mode, precision) = self.config;
+        let family = self.family;
+        let (trials, input) = self.input;
+
+        let mut sizes = vec![10, 100, 1000, 10000, input.len()];
+        sizes.retain(|&n| n <= input.len());
+        sizes.dedup();
+
+        let mut sum = vec![0.0; sizes.len()];
+        let mut sum_sq = vec![0.0; sizes.len()];
+
+        let seed = 0x5d0f6a0b3c2e1d47;
+        let mut rng = SplitMix64::new(seed);
+
+        for _ in 0..trials {
+            let mut hash = keygen(&mut rng);
+            let mut hll = HyperLogLog::new(precision);
+            let mut k = 0;
+            for (i, &x) in input.iter().enumerate() {
+                hll.insert(hash(x));
+                if i + 1 == sizes[k] {
+                    let err = hll.estimate() / (sizes[k] as f64) - 1.0;
+                    sum[k] += err;
+                    sum_sq[k] += err * err;
+                    k += 1;
+                }
+            }
+        }
+
+        for (k, &n) in sizes.iter().enumerate() {
+            let bias = sum[k] / f64::from(trials);
+            let rmse = (sum_sq[k] / f64::from(trials)).sqrt();
+
+            match mode {
+                OutputMode::Pretty => {
+                    println!(
+                        "Family: {}, precision: {}, cardinality: {}; bias: {:.6}, rmse: {:.6}",
+                        family, precision, n, bias, rmse
+                    );
+                }
+                OutputMode::Csv => {
+                    println!("{},{},{},{},{}", family, precision, n, bias, rmse);
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(3.0)),
+                            ("family", Value::Str(family)),
+                            ("precision", Value::Num(precision as f64)),
+                            ("cardinality", Value::Num(n as f64)),
+                            ("key_seed", Value::Str(&format!("{:#018x}", seed))),
+                            ("bias", Value::Num(bias)),
+                            ("rmse", Value::Num(rmse)),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn split_words(raw: &[u8]) -> Vec<&[u8]> {
+    raw.split(|b| b.is_ascii_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// Packs a word into a 64-bit key by xor-ing its big-endian 8-byte chunks.
+pub fn word_key(word: &[u8]) -> u64 {
+    let mut key = 0;
+    for chunk in word.chunks(8) {
+        let mut buf = [0; 8];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        key ^= BigEndian::read_u64(&buf);
+    }
+    key
+}
+
+pub fn experiment_3(mode: OutputMode, input_raw: &[u8]) {
+    // Distinct keys in order of first occurrence, so prefixes have known cardinality.
+
+    let mut seen = HashSet::new();
+    let input = split_words(input_raw)
+        .into_iter()
+        .map(word_key)
+        .filter(|&x| seen.insert(x))
+        .collect::<Vec<_>>();
+
+    let trials = 20;
+
+    if mode.is_csv() {
+        println!("family,precision,cardinality,bias,rmse");
+    }
+
+    for &precision in &[10, 14] {
+        let config = (mode, precision);
+
+        {
+            let spec = Spec3 {
+                config,
+                family: "shift",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.next_odd_u64();
+                move |x| imp::shift_u64(Bits::MAX, a, x)
+            });
+        }
+        {
+            let spec = Spec3 {
+                config,
+                family: "mmp",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.below_m89();
+                let b = rng.below_m89();
+                move |x| imp::mmp_p89_u64(Bits::MAX, a, b, x)
+            });
+        }
+        {
+            let spec = Spec3 {
+                config,
+                family: "poly",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.below_m89();
+                let b = rng.below_m89();
+                let c = rng.below_m89();
+                move |x| {
+                    let mut h = imp::PolyU64::new(a, b, c);
+                    h.write_u64(x);
+                    h.finish(Bits::MAX)
+                }
+            });
+        }
+        {
+            let spec = Spec3 {
+                config,
+                family: "tabulation",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let mut t = Box::new([[0; 256]; 8]);
+                for row in t.iter_mut() {
+                    rng.fill_u64(row);
+                }
+                let h = imp::TabulationU64::new(t);
+                move |x| h.hash(Bits::MAX, x)
+            });
+        }
+
+        #[allow(deprecated)]
+        {
+            let spec = Spec3 {
+                config,
+                family: "siphash",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.next_u64();
+                let b = rng.next_u64();
+                move |x| {
+                    let mut h = SipHasher::new_with_keys(a, b);
+                    h.write_u64(x);
+                    h.finish()
+                }
+            });
+        }
+    }
+}
+
+////////////////////////////////////////
+// Experiment 4
+////////////////////////////////////////
+
+// Two shingle sets and their exact Jaccard similarity.
+pub type SetPair = (Vec<u64>, Vec<u64>, f64);
+
+pub struct Spec4<'a> {
+    config: (OutputMode, usize),
+    family: &'a str,
+    input: (u32, &'a [SetPair]),
+}
+
+impl<'a> Spec4<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(u64) -> u64,
+    {
+        let (mode, k) = self.config;
+        let family = self.family;
+        let (trials, input) = self.input;
+
+        // Inputs of fewer than 2 * 200 words have no window pairs.
+        if input.is_empty() {
+            return;
+        }
+
+        let seed = 0x0c9a4f1e26b8d375;
+        let mut rng = SplitMix64::new(seed);
+
+        for &scheme in &["k-hash", "one-permutation"] {
+            let mut sum = 0.0;
+            let mut sum_sq = 0.0;
+
+            for _ in 0..trials {
+                let minhash = match scheme {
+                    "k-hash" => MinHasher::k_hash((0..k).map(|_| keygen(&mut rng)).collect()),
+                    _ => MinHasher::one_permutation(k, keygen(&mut rng)),
+                };
+
+                for &(ref a, ref b, exact) in input {
+                    let estimate = minhash::jaccard(&minhash.signature(a), &minhash.signature(b));
+                    let err = estimate - exact;
+                    sum += err;
+                    sum_sq += err * err;
+                }
+            }
+
+            let count = f64::from(trials) * input.len() as f64;
+            let bias = sum / count;
+            let rmse = (sum_sq / count).sqrt();
+
+            match mode {
+                OutputMode::Pretty => {
+                    println!(
+                        "Family: {}, scheme: {}, k: {}; bias: {:.6}, rmse: {:.6}",
+                        family, scheme, k, bias, rmse
+                    );
+                }
+                OutputMode::Csv => {
+                    println!("{},{},{},{},{}", family, scheme, k, bias, rmse);
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(4.0)),
+                            ("family", Value::Str(family)),
+                            ("scheme", Value::Str(scheme)),
+                            ("k", Value::Num(k as f64)),
+                            ("key_seed", Value::Str(&format!("{:#018x}", seed))),
+                            ("bias", Value::Num(bias)),
+                            ("rmse", Value::Num(rmse)),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Keys of the word w-shingles in words[start..start + len].
+pub fn shingles(words: &[u64], w: usize, start: usize, len: usize) -> Vec<u64> {
+    let mut set = words[start..start + len]
+        .windows(w)
+        .map(|window| {
+            window
+                .iter()
+                .enumerate()
+                .fold(0, |key, (i, &x)| key ^ x.rotate_left(21 * i as u32))
+        })
+        .collect::<Vec<_>>();
+    set.sort();
+    set.dedup();
+    set
+}
+
+pub fn jaccard_exact(a: &[u64], b: &[u64]) -> f64 {
+    let a = a.iter().collect::<HashSet<_>>();
+    let b = b.iter().collect::<HashSet<_>>();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+pub fn experiment_4(mode: OutputMode, input_raw: &[u8]) {
+    // Pairs of overlapping word windows; the shift controls the similarity.
+
+    let words = split_words(input_raw)
+        .into_iter()
+        .map(word_key)
+        .collect::<Vec<_>>();
+
+    let len = 200;
+    let mut input = Vec::new();
+    for start in (0..words.len().saturating_sub(2 * len)).step_by(1000) {
+        for &shift in &[len / 10, len / 4, len / 2, 3 * len / 4] {
+            let a = shingles(&words, 3, start, len);
+            let b = shingles(&words, 3, start + shift, len);
+            let exact = jaccard_exact(&a, &b);
+            input.push((a, b, exact));
+        }
+    }
+
+    let trials = 10;
+
+    if mode.is_csv() {
+        println!("family,scheme,k,bias,rmse");
+    }
+
+    for &k in &[16, 64] {
+        let config = (mode, k);
+
+        {
+            let spec = Spec4 {
+                config,
+                family: "shift",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.next_odd_u64();
+                move |x| imp::shift_u64(Bits::MAX, a, x)
+            });
+        }
+        {
+            let spec = Spec4 {
+                config,
+                family: "mmp",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.below_m89();
+                let b = rng.below_m89();
+                move |x| imp::mmp_p89_u64(Bits::MAX, a, b, x)
+            });
+        }
+        {
+            let spec = Spec4 {
+                config,
+                family: "tabulation",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let mut t = Box::new([[0; 256]; 8]);
+                for row in t.iter_mut() {
+                    rng.fill_u64(row);
+                }
+                let h = imp::TabulationU64::new(t);
+                move |x| h.hash(Bits::MAX, x)
+            });
+        }
+    }
+}
+
+////////////////////////////////////////
+// Experiment 5
+////////////////////////////////////////
+
+pub struct Spec5<'a> {
+    config: (OutputMode, usize, usize),
+    family: &'a str,
+    input: (&'a HashSet<(usize, usize)>, &'a [Vec<u64>]),
+}
+
+impl<'a> Spec5<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(u64) -> u64,
+    {
+        let (mode, bands, rows) = self.config;
+        let family = self.family;
+        let (similar, input) = self.input;
+
+        // Without similar pairs the recall is undefined.
+        if similar.is_empty() {
+            return;
+        }
+
+        let seed = 0x7b1e3a95c4d20f68;
+        let mut rng = SplitMix64::new(seed);
+
+        let minhash = MinHasher::one_permutation(bands * rows, keygen(&mut rng));
+        let mut index = LshIndex::new(bands, rows);
+
+        // Query before inserting, so every pair is reported once.
+
+        let mut found = 0;
+        let mut candidates = 0;
+
+        for (id, set) in input.iter().enumerate() {
+            let signature = minhash.signature(set);
+            for other in index.query(&signature) {
+                candidates += 1;
+                if similar.contains(&(other, id)) {
+                    found += 1;
+                }
+            }
+            index.insert(signature);
+        }
+
+        let recall = found as f64 / similar.len() as f64;
+        let false_candidates = candidates - found;
+        let threshold = index.threshold();
+
+        match mode {
+            OutputMode::Pretty => {
+                println!(
+                    "Family: {}, bands: {}, rows: {}, threshold: {:.3}; recall: {:.6}, candidates: {}, false candidates: {}",
+                    family, bands, rows, threshold, recall, candidates, false_candidates
+                );
+            }
+            OutputMode::Csv => {
+                println!(
+                    "{},{},{},{},{},{},{}",
+                    family, bands, rows, threshold, recall, candidates, false_candidates
+                );
+            }
+            OutputMode::Json | OutputMode::JsonLines => {
+                json::row(
+                    mode,
+                    &[
+                        ("experiment", Value::Num(5.0)),
+                        ("family", Value::Str(family)),
+                        ("bands", Value::Num(bands as f64)),
+                        ("rows", Value::Num(rows as f64)),
+                        ("key_seed", Value::Str(&format!("{:#018x}", seed))),
+                        ("threshold", Value::Num(threshold)),
+                        ("recall", Value::Num(recall)),
+                        ("candidates", Value::Num(f64::from(candidates))),
+                        ("false_candidates", Value::Num(f64::from(false_candidates))),
+                    ],
+                );
+            }
+        }
+    }
+}
+
+pub fn experiment_5(mode: OutputMode, input_raw: &[u8]) {
+    // Overlapping word windows as documents. Windows that do not overlap are taken
+    // to be dissimilar, so only neighbouring windows are compared exactly.
+
+    let words = split_words(input_raw)
+        .into_iter()
+        .map(word_key)
+        .collect::<Vec<_>>();
+
+    let len = 200;
+    let step = 40;
+    let min = 0.5;
+
+    let input = (0..words.len().saturating_sub(len))
+        .step_by(step)
+        .map(|start| shingles(&words, 3, start, len))
+        .collect::<Vec<_>>();
+
+    let mut similar = HashSet::new();
+    for i in 0..input.len() {
+        for j in i + 1..cmp::min(i + len / step, input.len()) {
+            if jaccard_exact(&input[i], &input[j]) >= min {
+                similar.insert((i, j));
+            }
+        }
+    }
+
+    if mode.is_csv() {
+        println!("family,bands,rows,threshold,recall,candidates,false_candidates");
+    }
+
+    for &(bands, rows) in &[(32, 2), (16, 4), (8, 8)] {
+        let config = (mode, bands, rows);
+
+        {
+            let spec = Spec5 {
+                config,
+                family: "shift",
+                input: (&similar, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.next_odd_u64();
+                move |x| imp::shift_u64(Bits::MAX, a, x)
+            });
+        }
+        {
+            let spec = Spec5 {
+                config,
+                family: "mmp",
+                input: (&similar, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.below_m89();
+                let b = rng.below_m89();
+                move |x| imp::mmp_p89_u64(Bits::MAX, a, b, x)
+            });
+        }
+        {
+            let spec = Spec5 {
+                config,
+                family: "tabulation",
+                input: (&similar, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let mut t = Box::new([[0; 256]; 8]);
+                for row in t.iter_mut() {
+                    rng.fill_u64(row);
+                }
+                let h = imp::TabulationU64::new(t);
+                move |x| h.hash(Bits::MAX, x)
+            });
+        }
+    }
+}
+
+////////////////////////////////////////
+// Experiment 6
+////////////////////////////////////////
+
+pub struct Spec6<'a> {
+    config: (OutputMode, usize),
+    family: &'a str,
+    input: (u32, &'a [Vec<u8>]),
+}
+
+impl<'a> Spec6<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(&[u8], usize) -> u64,
+    {
+        let (mode, l) = self.config;
+        let family = self.family;
+        let (trials, input) = self.input;
+
+        let seed = 0x2f8e61d7a05c94b3;
+        let mut rng = SplitMix64::new(seed);
+
+        // Hashing the vocabulary as one document leaves one entry per occupied bucket.
+
+        let mut occupied = 0;
+        for _ in 0..trials {
+            let hasher = FeatureHasher::new(l, keygen(&mut rng));
+            occupied += hasher.transform(input).len();
+        }
+
+        let n = input.len() as f64;
+        let m = (1u64 << l) as f64;
+        let occupied = occupied as f64 / f64::from(trials);
+        let rate = 1.0 - occupied / n;
+        let expected = 1.0 - m * (1.0 - (1.0 - 1.0 / m).powf(n)) / n;
+
+        match mode {
+            OutputMode::Pretty => {
+                println!(
+                    "Family: {}, dimension: 2^{}, tokens: {}; collision rate: {:.6}, expected: {:.6}",
+                    family, l, n, rate, expected
+                );
+            }
+            OutputMode::Csv => {
+                println!("{},{},{},{},{}", family, l, n, rate, expected);
+            }
+            OutputMode::Json | OutputMode::JsonLines => {
+                json::row(
+                    mode,
+                    &[
+                        ("experiment", Value::Num(6.0)),
+                        ("family", Value::Str(family)),
+                        ("bits", Value::Num(l as f64)),
+                        ("tokens", Value::Num(n)),
+                        ("key_seed", Value::Str(&format!("{:#018x}", seed))),
+                        ("rate", Value::Num(rate)),
+                        ("expected", Value::Num(expected)),
+                    ],
+                );
+            }
+        }
+    }
+}
+
+pub fn experiment_6(mode: OutputMode, input_raw: &[u8]) {
+    // Collisions among the distinct tokens of the input.
+
+    let mut input = features::tokenize(input_raw);
+    input.sort();
+    input.dedup();
+
+    let trials = 20;
+
+    if mode.is_csv() {
+        println!("family,bits,tokens,rate,expected");
+    }
+
+    for &l in &[10, 14, 16, 18, 20] {
+        let config = (mode, l);
+
+        {
+            let spec = Spec6 {
+                config,
+                family: "poly",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.below_m89();
+                let b = rng.below_m89();
+                let c = rng.below_m89();
+                move |token: &[u8], l| features::poly_bytes(Bits::new(l).unwrap(), a, b, c, token)
+            });
+        }
+        {
+            let spec = Spec6 {
+                config,
+                family: "vector-shift",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let mut a = [0; 65];
+                rng.fill_u64(&mut a);
+                move |token: &[u8], l| {
+                    u64::from(features::vector_shift_bytes(
+                        Bits::new(l).unwrap(),
+                        a,
+                        token,
+                    ))
+                }
+            });
+        }
+
+        #[allow(deprecated)]
+        {
+            let spec = Spec6 {
+                config,
+                family: "siphash",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.next_u64();
+                let b = rng.next_u64();
+                move |token: &[u8], l| {
+                    let mut h = SipHasher::new_with_keys(a, b);
+                    h.write(token);
+                    h.finish() >> (64 - l)
+                }
+            });
+        }
+    }
+}
+
+////////////////////////////////////////
+// Experiment 7
+////////////////////////////////////////
+
+pub struct Spec7<'a> {
+    config: (OutputMode, usize),
+    family: &'a str,
+    input: (u32, &'a [u64]),
+}
+
+impl<'a> Spec7<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(u64) -> u64,
+    {
+        let (mode, nodes) = self.config;
+        let family = self.family;
+        let (trials, input) = self.input;
+
+        let seed = 0x41c7e9b2d8f3a065;
+        let mut rng = SplitMix64::new(seed);
+
+        let replicas = 100;
+        let mean = input.len() as f64 / nodes as f64;
+
+        let mut max = [0.0; 3];
+        for _ in 0..trials {
+            let hash = keygen(&mut rng);
+            let ring = ConsistentRing::new(nodes, replicas, &hash);
+
+            let mut loads = vec![vec![0; nodes]; 3];
+            for &x in input {
+                let h = hash(x);
+                loads[0][ring.node(h)] += 1;
+                loads[1][sharding::jump_hash(h, nodes as u32) as usize] += 1;
+                loads[2][sharding::rendezvous(h, nodes, &hash)] += 1;
+            }
+
+            for (max, loads) in max.iter_mut().zip(&loads) {
+                *max += f64::from(*loads.iter().max().unwrap()) / mean;
+            }
+        }
+
+        for (&method, &max) in ["ring", "jump", "rendezvous"].iter().zip(&max) {
+            let max = max / f64::from(trials);
+
+            match mode {
+                OutputMode::Pretty => {
+                    println!(
+                        "Family: {}, method: {}, nodes: {}; max/mean load: {:.6}",
+                        family, method, nodes, max
+                    );
+                }
+                OutputMode::Csv => {
+                    println!("{},{},{},{}", family, method, nodes, max);
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(7.0)),
+                            ("family", Value::Str(family)),
+                            ("method", Value::Str(method)),
+                            ("nodes", Value::Num(nodes as f64)),
+                            ("key_seed", Value::Str(&format!("{:#018x}", seed))),
+                            ("max_load", Value::Num(max)),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn experiment_7(mode: OutputMode, input_raw: &[u8]) {
+    // Distinct word keys, so loads only reflect the hash functions.
+
+    let mut input = split_words(input_raw)
+        .into_iter()
+        .map(word_key)
+        .collect::<Vec<_>>();
+    input.sort();
+    input.dedup();
+
+    let trials = 10;
+
+    if mode.is_csv() {
+        println!("family,method,nodes,max_load");
+    }
+
+    for &nodes in &[16, 64] {
+        let config = (mode, nodes);
+
+        {
+            let spec = Spec7 {
+                config,
+                family: "shift",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.next_odd_u64();
+                move |x| imp::shift_u64(Bits::MAX, a, x)
+            });
+        }
+        {
+            let spec = Spec7 {
+                config,
+                family: "mmp",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.below_m89();
+                let b = rng.below_m89();
+                move |x| imp::mmp_p89_u64(Bits::MAX, a, b, x)
+            });
+        }
+        {
+            let spec = Spec7 {
+                config,
+                family: "tabulation",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let mut t = Box::new([[0; 256]; 8]);
+                for row in t.iter_mut() {
+                    rng.fill_u64(row);
+                }
+                let h = imp::TabulationU64::new(t);
+                move |x| h.hash(Bits::MAX, x)
+            });
+        }
+
+        #[allow(deprecated)]
+        {
+            let spec = Spec7 {
+                config,
+                family: "siphash",
+                input: (trials, &input[..]),
+            };
+
+            spec.sample(|rng| {
+                let a = rng.next_u64();
+                let b = rng.next_u64();
+                move |x| {
+                    let mut h = SipHasher::new_with_keys(a, b);
+                    h.write_u64(x);
+                    h.finish()
+                }
+            });
+        }
+    }
+}
+
+////////////////////////////////////////
+// Experiment 8
+////////////////////////////////////////
+
+// Description: Aggregate throughput with the input split into one contiguous part
+// per thread, each thread pinned to its own core where possible. Every thread
+// hashes its part as 4 KiB messages with the same key, so keys and tables are
+// shared and read by all cores at once. A trial lasts until the slowest thread
+// is done. Efficiency is the throughput relative to the single thread throughput
+// times the number of threads.
+
+pub struct Spec8<'a> {
+    config: (OutputMode, Timing, &'a [usize]),
+    family: &'a str,
+    input: (u32, &'a [u64]),
+}
+
+impl<'a> Spec8<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(&[u64]) -> u64 + Sync,
+    {
+        let (mode, timing, threads) = self.config;
+        let family = self.family;
+        let (reps, input) = self.input;
+
+        let seed = 0x93d4b7e15a2c6f08;
+        let mut rng = SplitMix64::new(seed);
+        let hash = keygen(&mut rng);
+        let key_seed = format!("{:#018x}", seed);
+
+        let cores = affinity::allowed_cores();
+        let bytes = (input.len() * 8) as f64;
+
+        let mut single = None;
+        let mut last = 0;
+        for &requested in threads {
+            // Short inputs split into fewer chunks than requested threads, and one
+            // thread runs per chunk; report and scale by the threads that ran.
+            let threads = input.len().div_ceil(input.len().div_ceil(requested));
+            if threads == last {
+                continue;
+            }
+            last = threads;
+
+            let (mut samples, pinned) = time_threads(timing, reps, threads, &cores, input, &hash);
+            for sample in &mut samples {
+                *sample /= bytes;
+            }
+            let stats = Stats::from_samples(&mut samples);
+
+            // Bytes per time unit from time per byte, i.e. GB/s when timed in ns.
+            let rate = 1.0 / stats.median;
+            let single = *single.get_or_insert(rate);
+            let efficiency = rate / (single * threads as f64);
+
+            match mode {
+                OutputMode::Pretty => {
+                    println!(
+                        "Family: {}, threads: {}, pinned: {}; throughput: {:.3} {}, \
+                         efficiency: {:.3}; {}/byte: {}",
+                        family,
+                        threads,
+                        pinned,
+                        rate,
+                        timing.clock.rate_unit(),
+                        efficiency,
+                        timing.clock.unit(),
+                        stats
+                    );
+                }
+                OutputMode::Csv => {
+                    let pinned = if pinned { "TRUE" } else { "FALSE" };
+                    println!(
+                        "{},{},{},{},{},{}",
+                        family,
+                        threads,
+                        pinned,
+                        rate,
+                        efficiency,
+                        stats.csv()
+                    );
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    let mut fields = vec![
+                        ("experiment", Value::Num(8.0)),
+                        ("family", Value::Str(family)),
+                        ("threads", Value::Num(threads as f64)),
+                        ("pinned", Value::Bool(pinned)),
+                        ("key_seed", Value::Str(&key_seed)),
+                        ("unit", Value::Str(timing.clock.unit())),
+                        (timing.clock.rate_column(), Value::Num(rate)),
+                        ("efficiency", Value::Num(efficiency)),
+                    ];
+                    fields.extend_from_slice(&stats.json());
+                    json::row(mode, &fields);
+                }
+            }
+        }
+    }
+}
+
+// Times of each trial until the slowest thread is done, per repetition, and
+// whether every thread could be pinned.
+fn time_threads<H>(
+    timing: Timing,
+    reps: u32,
+    threads: usize,
+    cores: &[usize],
+    input: &[u64],
+    hash: &H,
+) -> (Vec<f64>, bool)
+where
+    H: Fn(&[u64]) -> u64 + Sync,
+{
+    // The barrier waits for one thread per chunk, which may be fewer than threads.
+    let chunks = input
+        .chunks(input.len().div_ceil(threads))
+        .collect::<Vec<_>>();
+    let runs = (timing.warmup + timing.trials) as usize;
+    let barrier = Barrier::new(chunks.len());
+
+    let results = thread::scope(|scope| {
+        let barrier = &barrier;
+        let handles = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, part)| {
+                scope.spawn(move || {
+                    let pinned = !cores.is_empty() && affinity::pin_to_core(cores[i % cores.len()]);
+                    let mut state = 0;
+                    let times = (0..runs)
+                        .map(|_| {
+                            barrier.wait();
+                            time_reps(timing.clock, reps, || {
+                                for message in part.chunks(512) {
+                                    state ^= hash(message);
+                                }
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    let _ = test::black_box(state);
+                    (times, pinned)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let samples = (timing.warmup as usize..runs)
+        .map(|run| {
+            results
+                .iter()
+                .map(|(times, _)| times[run])
+                .fold(0.0, f64::max)
+        })
+        .collect();
+    let pinned = results.iter().all(|&(_, pinned)| pinned);
+    (samples, pinned)
+}
+
+pub fn experiment_8(mode: OutputMode, timing: Timing, max_threads: usize, input_raw: &[u8]) {
+    let input_8 = &input_raw[..input_raw.len() & !7];
+
+    let mut input_64 = vec![0; input_8.len() / 8];
+
+    BigEndian::read_u64_into(input_8, &mut input_64[..]);
+
+    // 1, 2, 4, ... threads, and max_threads itself.
+    let mut threads = (0..)
+        .map(|i| 1 << i)
+        .take_while(|&n| n < max_threads)
+        .collect::<Vec<_>>();
+    threads.push(max_threads);
+
+    let reps = 40;
+
+    let config = (mode, timing, &threads[..]);
+
+    if mode.is_csv() {
+        println!(
+            "family,threads,pinned,{},efficiency,{},mean,stddev,min,ci_low,ci_high",
+            timing.clock.rate_column(),
+            timing.clock.column()
+        );
+    }
+
+    {
+        let spec = Spec8 {
+            config,
+            family: "poly",
+            input: (reps, &input_64[..]),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            let c = rng.below_m89();
+            let l = Bits::new(32).unwrap();
+            move |input: &[u64]| {
+                let mut h = imp::PolyU64::new(a, b, c);
+                for &x in input {
+                    h.write_u64(x);
+                }
+                h.finish(l)
+            }
+        });
+    }
+    {
+        let spec = Spec8 {
+            config,
+            family: "poly-shift-triple",
+            input: (reps, &input_64[..]),
+        };
+
+        spec.sample(|rng| {
+            let a = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+            let b = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+            let c = rng.below_m89();
+            move |input: &[u64]| {
+                let mut h = imp::PolyShiftU64::new(a, b, c);
+                for &x in input {
+                    h.write_u64(x);
+                }
+                u64::from(h.finish(Bits::MAX))
+            }
+        });
+    }
+    {
+        let spec = Spec8 {
+            config,
+            family: "preproc-poly",
+            input: (reps, &input_64[..]),
+        };
+
+        spec.sample(|rng| {
+            let mut prep1 = [0; 65];
+            let mut prep2 = [0; 65];
+            rng.fill_u64(&mut prep1);
+            rng.fill_u64(&mut prep2);
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            let c = rng.below_m89();
+            let l = Bits::new(32).unwrap();
+            move |input: &[u64]| {
+                let mut h = imp::PreprocPolyU64D32::new(prep1, prep2, a, b, c);
+                for &x in input {
+                    h.write_u64(x);
+                }
+                h.finish(l)
+            }
+        });
+    }
+    {
+        let spec = Spec8 {
+            config,
+            family: "tabulation",
+            input: (reps, &input_64[..]),
+        };
+
+        spec.sample(|rng| {
+            let mut t = Box::new([[0; 256]; 8]);
+            for row in t.iter_mut() {
+                rng.fill_u64(row);
+            }
+            let h = imp::TabulationU64::new(t);
+            move |input: &[u64]| input.iter().fold(0, |acc, &x| acc ^ h.hash(Bits::MAX, x))
+        });
+    }
+
+    #[allow(deprecated)]
+    {
+        let spec = Spec8 {
+            config,
+            family: "siphash",
+            input: (reps, &input_64[..]),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u64();
+            let b = rng.next_u64();
+            move |input: &[u64]| {
+                let mut h = SipHasher::new_with_keys(a, b);
+                for &x in input {
+                    h.write_u64(x);
+                }
+                h.finish()
+            }
+        });
+    }
+}
+
+////////////////////////////////////////
+// Experiment 9
+////////////////////////////////////////
+
+// Description: Empirical universality. For fixed pairs x != y, draws many keys and
+// estimates the collision probability Pr[h(x) = h(y)] with m = 2^l outputs, and
+// how uniform the pair (h(x), h(y)) is over m'^2 cells with m' = 2^l' outputs as
+// chi-square per degree of freedom (about 1 if uniform). A family is flagged if
+// its collision rate exceeds its bound by more than four standard errors, or if
+// it claims strong universality and the chi-square exceeds its degrees of
+// freedom by more than four standard deviations.
+// Parameters: claim is (collision bound times m, strongly universal).
+
+pub type Pair<'a> = (&'a str, u64, u64);
+
+pub struct Spec9<'a> {
+    config: (OutputMode, usize, usize),
+    family: &'a str,
+    claim: (f64, bool),
+    input: (u32, &'a [Pair<'a>]),
+}
+
+impl<'a> Spec9<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(usize, u64) -> u64,
+    {
+        let (mode, l, l_pair) = self.config;
+        let family = self.family;
+        let (bound, strong) = self.claim;
+        let (samples, pairs) = self.input;
+
+        let seed = 0x6c8e9cf570932bd5;
+        let key_seed = format!("{:#018x}", seed);
+
+        let m = (1u64 << l) as f64;
+        let bound = bound / m;
+        let cells = 1 << (2 * l_pair);
+
+        for &(pair, x, y) in pairs {
+            let mut rng = SplitMix64::new(seed);
+            let mut collisions = 0;
+            let mut counts = vec![0u32; cells];
+            for _ in 0..samples {
+                let hash = keygen(&mut rng);
+                if hash(l, x) == hash(l, y) {
+                    collisions += 1;
+                }
+                counts[(hash(l_pair, x) << l_pair | hash(l_pair, y)) as usize] += 1;
+            }
+
+            let n = f64::from(samples);
+            let rate = f64::from(collisions) / n;
+            let expected = n / cells as f64;
+            let chi2 = counts
+                .iter()
+                .map(|&count| (f64::from(count) - expected).powi(2) / expected)
+                .sum::<f64>();
+            let df = (cells - 1) as f64;
+            let uniformity = chi2 / df;
+
+            let exceeds = rate > bound + 4.0 * (bound * (1.0 - bound) / n).sqrt();
+            let skewed = strong && chi2 > df + 4.0 * (2.0 * df).sqrt();
+            let verdict = match (exceeds, skewed) {
+                (true, _) => "exceeds bound",
+                (false, true) => "not uniform",
+                (false, false) => "ok",
+            };
+
+            match mode {
+                OutputMode::Pretty => {
+                    println!(
+                        "Family: {}, pair: {}; collision rate: {:.6} (bound: {:.6}, ratio: {:.3}), \
+                         pair chi2/df: {:.3}; {}",
+                        family,
+                        pair,
+                        rate,
+                        bound,
+                        rate / bound,
+                        uniformity,
+                        verdict
+                    );
+                }
+                OutputMode::Csv => {
+                    println!(
+                        "{},{},{},{},{},{},{}",
+                        family,
+                        pair,
+                        rate,
+                        bound,
+                        rate / bound,
+                        uniformity,
+                        verdict
+                    );
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(9.0)),
+                            ("family", Value::Str(family)),
+                            ("pair", Value::Str(pair)),
+                            ("bits", Value::Num(l as f64)),
+                            ("key_seed", Value::Str(&key_seed)),
+                            ("collision_rate", Value::Num(rate)),
+                            ("bound", Value::Num(bound)),
+                            ("ratio", Value::Num(rate / bound)),
+                            ("pair_chi2_df", Value::Num(uniformity)),
+                            ("verdict", Value::Str(verdict)),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn experiment_9(mode: OutputMode) {
+    // Structured pairs that hit each family where it splits its input, plus a
+    // random pair.
+
+    let mut rng = SplitMix64::new(0x0b4f2a6d93e8c517);
+    let x = rng.next_u64();
+    let pairs = [
+        ("zero-one", 0, 1),
+        ("low-bit", x, x ^ 1),
+        ("high-bit", x, x ^ 1 << 63),
+        ("word-halves", x, x ^ 1 << 32),
+        ("chunks-30", x, x ^ 1 << 30 ^ 1 << 60),
+        ("stride-2^56", x, x.wrapping_add(1 << 56)),
+        ("zero-ones", 0, !0),
+        ("random", x, rng.next_u64()),
+    ];
+
+    let samples = 1 << 17;
+    let config = (mode, 8, 4);
+
+    if mode.is_csv() {
+        println!("family,pair,collision_rate,bound,ratio,pair_chi2_df,verdict");
+    }
+
+    // Multiply-Shift
+
+    {
+        let spec = Spec9 {
+            config,
+            family: "shift",
+            claim: (2.0, false),
+            input: (samples, &pairs),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_odd_u64();
+            move |l, x| imp::shift_u64(Bits::new(l).unwrap(), a, x)
+        });
+    }
+    {
+        let spec = Spec9 {
+            config,
+            family: "shift-strong",
+            claim: (1.0, true),
+            input: (samples, &pairs),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u128();
+            let b = rng.next_u128();
+            move |l, x| imp::shift_strong_u64_128(Bits::new(l).unwrap(), a, b, x)
+        });
+    }
+
+    // Multiply-Mod-Prime
+
+    {
+        let spec = Spec9 {
+            config,
+            family: "mmp",
+            claim: (1.0, true),
+            input: (samples, &pairs),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            move |l, x| imp::mmp_p89_u64(Bits::new(l).unwrap(), a, b, x)
+        });
+    }
+    {
+        let spec = Spec9 {
+            config,
+            family: "mmp-triple",
+            claim: (1.0, true),
+            input: (samples, &pairs),
+        };
+
+        spec.sample(|rng| {
+            let a = [rng.below_m31(), rng.below_m31(), rng.below_m31()];
+            let b = [rng.below_m31(), rng.below_m31(), rng.below_m31()];
+            move |l, x| u64::from(imp::mmp_p31_u64(Bits::new(l).unwrap(), a, b, x))
+        });
+    }
+
+    // Polynomial
+
+    {
+        let spec = Spec9 {
+            config,
+            family: "poly",
+            claim: (1.0, true),
+            input: (samples, &pairs),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            let c = rng.below_m89();
+            move |l, x| {
+                let mut h = imp::PolyU64::new(a, b, c);
+                h.write_u64(x);
+                h.finish(Bits::new(l).unwrap())
+            }
+        });
+    }
+
+    // Simple Tabulation
+
+    {
+        let spec = Spec9 {
+            config,
+            family: "tabulation",
+            claim: (1.0, true),
+            input: (samples, &pairs),
+        };
+
+        spec.sample(|rng| {
+            let mut t = Box::new([[0; 256]; 8]);
+            for row in t.iter_mut() {
+                rng.fill_u64(row);
+            }
+            let h = imp::TabulationU64::new(t);
+            move |l, x| h.hash(Bits::new(l).unwrap(), x)
+        });
+    }
+
+    // SipHash
+
+    #[allow(deprecated)]
+    {
+        let spec = Spec9 {
+            config,
+            family: "siphash",
+            claim: (1.0, true),
+            input: (samples, &pairs),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u64();
+            let b = rng.next_u64();
+            move |l, x| {
+                let mut h = SipHasher::new_with_keys(a, b);
+                h.write_u64(x);
+                h.finish() >> (64 - l)
+            }
+        });
+    }
+}
+
+////////////////////////////////////////
+// Experiment 10
+////////////////////////////////////////
+
+// Description: Statistical quality of each family with one fixed key over the
+// input words: bucket chi-square per degree of freedom with m' = 2^l' buckets
+// (about 1 if uniform), and the strict avalanche criterion and bit independence
+// criterion matrices with m = 2^l outputs (see analysis). In CSV and JSON every
+// matrix entry is a row; for "sac", row is the input bit and column the output
+// bit, for "bic", both are output bits.
+// Parameters: in_bits is the width of the family's input; the words are
+// truncated to it.
+
+pub struct Spec10<'a> {
+    config: (OutputMode, usize, usize),
+    family: &'a str,
+    input: (usize, &'a [u64]),
+}
+
+impl<'a> Spec10<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(usize, u64) -> u64,
+    {
+        let (mode, l, l_chi) = self.config;
+        let family = self.family;
+        let (in_bits, words) = self.input;
+
+        let seed = 0x2f7a91c4e6b3d058;
+        let key_seed = format!("{:#018x}", seed);
+        let hash = keygen(&mut SplitMix64::new(seed));
+
+        // Distinct inputs, in input order.
+        let mask = !0 >> (64 - in_bits);
+        let mut seen = HashSet::new();
+        let inputs = words
+            .iter()
+            .map(|&x| x & mask)
+            .filter(|&x| seen.insert(x))
+            .collect::<Vec<_>>();
+
+        let buckets = 1 << l_chi;
+        let values = inputs.iter().map(|&x| hash(l_chi, x)).collect::<Vec<_>>();
+        let chi2_df = analysis::chi_square(&values, buckets) / (buckets - 1) as f64;
+
+        let n = cmp::min(inputs.len(), 1 << 14);
+        let avalanche = analysis::avalanche(|x| hash(l, x), in_bits, l, &inputs[..n]);
+
+        match mode {
+            OutputMode::Pretty => {
+                let sac = avalanche.sac.iter().flatten().map(|&p| (p - 0.5).abs());
+                let (sac_max, sac_sum) =
+                    sac.fold((0.0f64, 0.0), |(max, sum), d| (max.max(d), sum + d));
+                let bic_max = avalanche.bic.iter().flatten().cloned().fold(0.0, f64::max);
+                println!(
+                    "Family: {}, {} input bits, {} inputs; bucket chi2/df: {:.3}, \
+                     SAC |p - 1/2| max: {:.4} (mean: {:.4}), BIC |corr| max: {:.4}",
+                    family,
+                    in_bits,
+                    inputs.len(),
+                    chi2_df,
+                    sac_max,
+                    sac_sum / (in_bits * l) as f64,
+                    bic_max
+                );
+            }
+            OutputMode::Csv => {
+                println!("{},chi2_df,,,{}", family, chi2_df);
+                for (i, row) in avalanche.sac.iter().enumerate() {
+                    for (j, p) in row.iter().enumerate() {
+                        println!("{},sac,{},{},{}", family, i, j, p);
+                    }
+                }
+                for (j, row) in avalanche.bic.iter().enumerate() {
+                    for (k, corr) in row.iter().enumerate().skip(j + 1) {
+                        println!("{},bic,{},{},{}", family, j, k, corr);
+                    }
+                }
+            }
+            OutputMode::Json | OutputMode::JsonLines => {
+                let row = |statistic, cell: Option<(usize, usize)>, value| {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(10.0)),
+                            ("family", Value::Str(family)),
+                            ("key_seed", Value::Str(&key_seed)),
+                            ("statistic", Value::Str(statistic)),
+                            ("row", Value::opt(cell.map(|(i, _)| i as f64))),
+                            ("column", Value::opt(cell.map(|(_, j)| j as f64))),
+                            ("value", Value::Num(value)),
+                        ],
+                    );
+                };
+                row("chi2_df", None, chi2_df);
+                for (i, sac) in avalanche.sac.iter().enumerate() {
+                    for (j, &p) in sac.iter().enumerate() {
+                        row("sac", Some((i, j)), p);
+                    }
+                }
+                for (j, bic) in avalanche.bic.iter().enumerate() {
+                    for (k, &corr) in bic.iter().enumerate().skip(j + 1) {
+                        row("bic", Some((j, k)), corr);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn experiment_10(mode: OutputMode, input_raw: &[u8]) {
+    let input_8 = &input_raw[..input_raw.len() & !7];
+
+    let mut input_64 = vec![0; input_8.len() / 8];
+
+    BigEndian::read_u64_into(input_8, &mut input_64[..]);
+
+    let config = (mode, 16, 10);
+
+    if mode.is_csv() {
+        println!("family,statistic,row,column,value");
+    }
+
+    // Multiply-Shift
+
+    {
+        let spec = Spec10 {
+            config,
+            family: "shift-u32",
+            input: (32, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let a = OddU32::new(rng.next_u64() as u32 | 1).unwrap();
+            move |l, x| u64::from(imp::shift_u32(Bits::new(l).unwrap(), a, x as u32))
+        });
+    }
+    {
+        let spec = Spec10 {
+            config,
+            family: "shift-strong-u32",
+            input: (32, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u64();
+            let b = rng.next_u64();
+            move |l, x| u64::from(imp::shift_strong_u32(Bits::new(l).unwrap(), a, b, x as u32))
+        });
+    }
+    {
+        let spec = Spec10 {
+            config,
+            family: "shift",
+            input: (64, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_odd_u64();
+            move |l, x| imp::shift_u64(Bits::new(l).unwrap(), a, x)
+        });
+    }
+    {
+        let spec = Spec10 {
+            config,
+            family: "shift-strong",
+            input: (64, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u128();
+            let b = rng.next_u128();
+            move |l, x| imp::shift_strong_u64_128(Bits::new(l).unwrap(), a, b, x)
+        });
+    }
+
+    // Multiply-Mod-Prime
+
+    {
+        let spec = Spec10 {
+            config,
+            family: "mmp",
+            input: (64, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            move |l, x| imp::mmp_p89_u64(Bits::new(l).unwrap(), a, b, x)
+        });
+    }
+
+    // Polynomial
+
+    {
+        let spec = Spec10 {
+            config,
+            family: "poly",
+            input: (64, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            let c = rng.below_m89();
+            // On one word poly is mmp with the same a and b; hashing the input as
+            // two 32-bit words makes c take part.
+            move |l, x| {
+                let mut h = imp::PolyU64::new(a, b, c);
+                h.write_u64(x >> 32);
+                h.write_u64(x & 0xffffffff);
+                h.finish(Bits::new(l).unwrap())
+            }
+        });
+    }
+
+    // Simple Tabulation
+
+    {
+        let spec = Spec10 {
+            config,
+            family: "tabulation",
+            input: (64, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let mut t = Box::new([[0; 256]; 8]);
+            for row in t.iter_mut() {
+                rng.fill_u64(row);
+            }
+            let h = imp::TabulationU64::new(t);
+            move |l, x| h.hash(Bits::new(l).unwrap(), x)
+        });
+    }
+
+    // SipHash
+
+    #[allow(deprecated)]
+    {
+        let spec = Spec10 {
+            config,
+            family: "siphash",
+            input: (64, &input_64),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u64();
+            let b = rng.next_u64();
+            move |l, x| {
+                let mut h = SipHasher::new_with_keys(a, b);
+                h.write_u64(x);
+                h.finish() >> (64 - l)
+            }
+        });
+    }
+}
+
+////////////////////////////////////////
+// Experiment 11
+////////////////////////////////////////
+
+// Description: Test battery after SMHasher on the 32-bit hashes of each family
+// with one fixed key (see battery): the structured key sets, differential pairs
+// of keys one or two bits apart, and one key under many keys of the family. In
+// human-readable output, a table of the verdicts follows.
+
+pub struct Spec11<'a> {
+    config: OutputMode,
+    family: &'a str,
+}
+
+impl<'a> Spec11<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F) -> Vec<(&'static str, bool)>
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(&[u64]) -> u32,
+    {
+        let mode = self.config;
+        let family = self.family;
+
+        let seed = 0x71c3e58a0f2d94b6;
+        let key_seed = format!("{:#018x}", seed);
+        let hash = keygen(&mut SplitMix64::new(seed));
+
+        let mut outcomes = Vec::new();
+        for &(test, key_set) in battery::KEY_SETS.iter() {
+            let mut hashes = Vec::new();
+            key_set(&mut |group, key| hashes.push((group as u64) << 32 | u64::from(hash(key))));
+            outcomes.push((test, battery::evaluate(hashes)));
+        }
+        let (pairs, collisions) = battery::differential(&hash);
+        outcomes.push((
+            "differential",
+            battery::Outcome {
+                keys: pairs,
+                collisions,
+                expected: pairs as f64 / (1u64 << 32) as f64,
+                chi2_df: None,
+            },
+        ));
+        outcomes.push(("seed", battery::evaluate(battery::seeds(&mut keygen))));
+
+        for &(test, ref outcome) in &outcomes {
+            let verdict = if outcome.pass() { "pass" } else { "FAIL" };
+            match mode {
+                OutputMode::Pretty => {
+                    let chi2_df = outcome.chi2_df.map_or(String::new(), |chi2_df| {
+                        format!(", bucket chi2/df: {:.3}", chi2_df)
+                    });
+                    println!(
+                        "Family: {}, test: {}; {} keys, collisions: {} (expected: {:.1}){}; {}",
+                        family,
+                        test,
+                        outcome.keys,
+                        outcome.collisions,
+                        outcome.expected,
+                        chi2_df,
+                        verdict
+                    );
+                }
+                OutputMode::Csv => {
+                    println!(
+                        "{},{},{},{},{},{},{}",
+                        family,
+                        test,
+                        outcome.keys,
+                        outcome.collisions,
+                        outcome.expected,
+                        outcome
+                            .chi2_df
+                            .map_or(String::new(), |chi2_df| chi2_df.to_string()),
+                        verdict
+                    );
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(11.0)),
+                            ("family", Value::Str(family)),
+                            ("test", Value::Str(test)),
+                            ("key_seed", Value::Str(&key_seed)),
+                            ("keys", Value::Num(outcome.keys as f64)),
+                            ("collisions", Value::Num(outcome.collisions as f64)),
+                            ("expected", Value::Num(outcome.expected)),
+                            ("chi2_df", Value::opt(outcome.chi2_df)),
+                            ("verdict", Value::Str(verdict)),
+                        ],
+                    );
+                }
+            }
+        }
+
+        outcomes
+            .iter()
+            .map(|&(test, ref outcome)| (test, outcome.pass()))
+            .collect()
+    }
+}
+
+pub fn experiment_11(mode: OutputMode) {
+    let mut table = Vec::new();
+
+    if mode.is_csv() {
+        println!("family,test,keys,collisions,expected,chi2_df,verdict");
+    }
+
+    // Vectorized Multiply-Shift
+
+    {
+        let spec = Spec11 {
+            config: mode,
+            family: "vector-shift",
+        };
+
+        let verdicts = spec.sample(|rng| {
+            let mut a = [0; 65];
+            rng.fill_u64(&mut a);
+            move |key: &[u64]| {
+                let mut h = imp::VectorShiftU32D64::new(a);
+                for &x in key {
+                    h.write_u32((x >> 32) as u32);
+                    h.write_u32(x as u32);
+                }
+                h.finish(Bits::MAX)
+            }
+        });
+        table.push((spec.family, verdicts));
+    }
+
+    // Polynomial
+
+    {
+        let spec = Spec11 {
+            config: mode,
+            family: "poly",
+        };
+
+        let verdicts = spec.sample(|rng| {
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            let c = rng.below_m89();
+            let l = Bits::new(32).unwrap();
+            move |key: &[u64]| {
+                let mut h = imp::PolyU64::new(a, b, c);
+                for &x in key {
+                    h.write_u64(x);
+                }
+                h.finish(l) as u32
+            }
+        });
+        table.push((spec.family, verdicts));
+    }
+    {
+        let spec = Spec11 {
+            config: mode,
+            family: "preproc-poly",
+        };
+
+        let verdicts = spec.sample(|rng| {
+            let mut prep1 = [0; 65];
+            let mut prep2 = [0; 65];
+            rng.fill_u64(&mut prep1);
+            rng.fill_u64(&mut prep2);
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            let c = rng.below_m89();
+            let l = Bits::new(32).unwrap();
+            move |key: &[u64]| {
+                let mut h = imp::PreprocPolyU64D32::new(prep1, prep2, a, b, c);
+                for &x in key {
+                    h.write_u64(x);
+                }
+                h.finish(l) as u32
+            }
+        });
+        table.push((spec.family, verdicts));
+    }
+
+    // SipHash
+
+    #[allow(deprecated)]
+    {
+        let spec = Spec11 {
+            config: mode,
+            family: "siphash",
+        };
+
+        let verdicts = spec.sample(|rng| {
+            let a = rng.next_u64();
+            let b = rng.next_u64();
+            move |key: &[u64]| {
+                let mut h = SipHasher::new_with_keys(a, b);
+                for &x in key {
+                    h.write_u64(x);
+                }
+                (h.finish() >> 32) as u32
+            }
+        });
+        table.push((spec.family, verdicts));
+    }
+
+    if mode.is_pretty() {
+        println!();
+        print!("{:<14}", "Family");
+        for &(test, _) in &table[0].1 {
+            print!("{:>14}", test);
+        }
+        println!();
+        for &(family, ref verdicts) in &table {
+            print!("{:<14}", family);
+            for &(_, pass) in verdicts {
+                print!("{:>14}", if pass { "pass" } else { "FAIL" });
+            }
+            println!();
+        }
+    }
+}
+
+////////////////////////////////////////
+// Experiment 12
+////////////////////////////////////////
+
+// Description: Bucket loads. Hashes n distinct keys into m = 2^l buckets with one
+// fixed key per family and reports the maximum load, the number of empty buckets
+// and the variance of the loads, each next to its expectation for uniform and
+// independent buckets (see analysis). Key sets are random, or arithmetic
+// progressions x + i d, on which multiply-shift maps keys to a lattice.
+
+pub type KeySet = (String, Vec<u64>);
+
+pub struct Spec12<'a> {
+    config: (OutputMode, usize),
+    family: &'a str,
+    input: &'a [KeySet],
+}
+
+impl<'a> Spec12<'a> {
+    pub fn sample<F, H>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(usize, u64) -> u64,
+    {
+        let (mode, l) = self.config;
+        let family = self.family;
+        let key_sets = self.input;
+
+        let seed = 0xe8d7c2b5a4f39061;
+        let key_seed = format!("{:#018x}", seed);
+        let hash = keygen(&mut SplitMix64::new(seed));
+
+        let m = 1 << l;
+
+        for (name, keys) in key_sets {
+            let n = keys.len();
+            let mut loads = vec![0u32; m];
+            for &x in keys {
+                loads[hash(l, x) as usize] += 1;
+            }
+
+            let max_load = loads.iter().cloned().max().unwrap_or(0);
+            let empty = loads.iter().filter(|&&load| load == 0).count();
+            let mean = n as f64 / m as f64;
+            let variance = loads
+                .iter()
+                .map(|&load| (f64::from(load) - mean).powi(2))
+                .sum::<f64>()
+                / m as f64;
+
+            let expected_max_load = analysis::expected_max_load(n, m);
+            let expected_empty = analysis::expected_empty(n, m);
+            let expected_variance = analysis::expected_load_variance(n, m);
+
+            match mode {
+                OutputMode::Pretty => {
+                    println!(
+                        "Family: {}, keys: {}; max load: {} (expected: {:.2}), empty: {} \
+                         (expected: {:.0}), load variance: {:.4} (expected: {:.4})",
+                        family,
+                        name,
+                        max_load,
+                        expected_max_load,
+                        empty,
+                        expected_empty,
+                        variance,
+                        expected_variance
+                    );
+                }
+                OutputMode::Csv => {
+                    println!(
+                        "{},{},{},{},{},{},{},{},{},{}",
+                        family,
+                        name,
+                        n,
+                        m,
+                        max_load,
+                        expected_max_load,
+                        empty,
+                        expected_empty,
+                        variance,
+                        expected_variance
+                    );
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(12.0)),
+                            ("family", Value::Str(family)),
+                            ("keys", Value::Str(name)),
+                            ("key_seed", Value::Str(&key_seed)),
+                            ("n", Value::Num(n as f64)),
+                            ("buckets", Value::Num(m as f64)),
+                            ("max_load", Value::Num(f64::from(max_load))),
+                            ("expected_max_load", Value::Num(expected_max_load)),
+                            ("empty", Value::Num(empty as f64)),
+                            ("expected_empty", Value::Num(expected_empty)),
+                            ("load_variance", Value::Num(variance)),
+                            ("expected_variance", Value::Num(expected_variance)),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn experiment_12(mode: OutputMode) {
+    let l = 20;
+    let n = 1 << l;
+
+    // Random keys, and progressions with strides that are powers of two up to the
+    // largest that keeps n keys distinct, plus a random odd stride.
+
+    let mut rng = SplitMix64::new(0x3a6f0c9d81e5b247);
+    let mut seen = HashSet::new();
+    let mut random = Vec::with_capacity(n);
+    while random.len() < n {
+        let x = rng.next_u64();
+        if seen.insert(x) {
+            random.push(x);
+        }
+    }
+    let mut key_sets = vec![("random".to_string(), random)];
+    let start = rng.next_u64();
+    let strides = [
+        ("1", 1),
+        ("2^20", 1 << 20),
+        ("2^32", 1 << 32),
+        ("2^43", 1 << 43),
+        ("2^44", 1 << 44),
+        ("odd", rng.next_u64() | 1),
+    ];
+    for &(name, d) in strides.iter() {
+        let keys = (0..n as u64)
+            .map(|i| start.wrapping_add(i.wrapping_mul(d)))
+            .collect();
+        key_sets.push((format!("ap-{}", name), keys));
+    }
+
+    let config = (mode, l);
+
+    if mode.is_csv() {
+        println!(
+            "family,keys,n,buckets,max_load,expected_max_load,empty,expected_empty,\
+             load_variance,expected_variance"
+        );
+    }
+
+    // Multiply-Shift
+
+    {
+        let spec = Spec12 {
+            config,
+            family: "shift",
+            input: &key_sets,
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_odd_u64();
+            move |l, x| imp::shift_u64(Bits::new(l).unwrap(), a, x)
+        });
+    }
+    {
+        let spec = Spec12 {
+            config,
+            family: "shift-strong",
+            input: &key_sets,
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u128();
+            let b = rng.next_u128();
+            move |l, x| imp::shift_strong_u64_128(Bits::new(l).unwrap(), a, b, x)
+        });
+    }
+
+    // Multiply-Mod-Prime
+
+    {
+        let spec = Spec12 {
+            config,
+            family: "mmp",
+            input: &key_sets,
+        };
+
+        spec.sample(|rng| {
+            let a = rng.below_m89();
+            let b = rng.below_m89();
+            move |l, x| imp::mmp_p89_u64(Bits::new(l).unwrap(), a, b, x)
+        });
+    }
+    {
+        let spec = Spec12 {
+            config,
+            family: "mmp-triple",
+            input: &key_sets,
+        };
+
+        spec.sample(|rng| {
+            let a = [rng.below_m31(), rng.below_m31(), rng.below_m31()];
+            let b = [rng.below_m31(), rng.below_m31(), rng.below_m31()];
+            move |l, x| u64::from(imp::mmp_p31_u64(Bits::new(l).unwrap(), a, b, x))
+        });
+    }
+
+    // Polynomial
+
+    {
+        let spec = Spec12 {
+            config,
+            family: "poly-shift-triple",
+            input: &key_sets,
+        };
+
+        spec.sample(|rng| {
+            let a = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+            let b = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+            let c = rng.below_m89();
+            move |l, x| {
+                let mut h = imp::PolyShiftU64::new(a, b, c);
+                h.write_u64(x);
+                u64::from(h.finish(Bits::new(l).unwrap()))
+            }
+        });
+    }
+
+    // Simple Tabulation
+
+    {
+        let spec = Spec12 {
+            config,
+            family: "tabulation",
+            input: &key_sets,
+        };
+
+        spec.sample(|rng| {
+            let mut t = Box::new([[0; 256]; 8]);
+            for row in t.iter_mut() {
+                rng.fill_u64(row);
+            }
+            let h = imp::TabulationU64::new(t);
+            move |l, x| h.hash(Bits::new(l).unwrap(), x)
+        });
+    }
+
+    // SipHash
+
+    #[allow(deprecated)]
+    {
+        let spec = Spec12 {
+            config,
+            family: "siphash",
+            input: &key_sets,
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_u64();
+            let b = rng.next_u64();
+            move |l, x| {
+                let mut h = SipHasher::new_with_keys(a, b);
+                h.write_u64(x);
+                h.finish() >> (64 - l)
+            }
+        });
+    }
+}
+
+////////////////////////////////////////
+// Experiment 13
+////////////////////////////////////////
+
+// Description: Adversarial inputs (see adversary). With the key known, n preimages
+// of the hash of a random victim input, which all collide. With the key unknown,
+// n keys in arithmetic progression with each power-of-two stride, and their
+// colliding pairs on average and for the worst of the random keys. Both against
+// the colliding pairs of n random inputs to a random function, n (n - 1) / 2m. In
+// human-readable output only the worst stride on average is shown.
+// Parameters: keygen returns the hash and, for its key, preimages of a value.
+
+pub struct Spec13<'a> {
+    config: (OutputMode, usize),
+    family: &'a str,
+    input: (usize, usize, usize),
+}
+
+impl<'a> Spec13<'a> {
+    pub fn sample<F, H, P>(&self, mut keygen: F)
+    where
+        F: FnMut(&mut SplitMix64) -> (H, P),
+        H: Fn(usize, u64) -> u64,
+        P: Fn(usize, u64, usize) -> Vec<u64>,
+    {
+        let (mode, l) = self.config;
+        let family = self.family;
+        let (in_bits, n, trials) = self.input;
+
+        let seed = 0x5be0cd19137e2179;
+        let key_seed = format!("{:#018x}", seed);
+
+        let m = (1u64 << l) as f64;
+        let expected = (n * (n - 1)) as f64 / 2.0 / m;
+
+        let mut rng = SplitMix64::new(seed);
+        let (hash, preimages) = keygen(&mut rng);
+        let max = !0 >> (64 - in_bits);
+        let victim = rng.next_u64() & max;
+        let keys = preimages(l, hash(l, victim), n);
+        assert!(keys.iter().all(|&x| x <= max));
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), n);
+        let known = adversary::colliding_pairs(keys.iter().map(|&x| hash(l, x)).collect());
+
+        let strides = adversary::stride_collisions(
+            |rng| {
+                let (hash, _) = keygen(rng);
+                move |x| hash(l, x)
+            },
+            in_bits,
+            n,
+            trials,
+        );
+
+        let mut rows = vec![("known-key".to_string(), known as f64, known)];
+        rows.extend(
+            strides
+                .iter()
+                .map(|&(s, mean, max)| (format!("stride-2^{}", s), mean, max)),
+        );
+
+        match mode {
+            OutputMode::Pretty => {
+                let worst = strides.iter().fold((0, 0.0, 0), |worst, &stride| {
+                    if stride.1 > worst.1 {
+                        stride
+                    } else {
+                        worst
+                    }
+                });
+                println!(
+                    "Family: {}, known key; {} keys, colliding pairs: {} (random: {:.1})",
+                    family, n, known, expected
+                );
+                println!(
+                    "Family: {}, unknown key, worst stride 2^{}; {} keys, colliding pairs: {:.1}, \
+                     worst key: {} (random: {:.1})",
+                    family, worst.0, n, worst.1, worst.2, expected
+                );
+            }
+            OutputMode::Csv => {
+                for (attack, pairs, worst) in rows {
+                    println!(
+                        "{},{},{},{},{},{}",
+                        family, attack, n, pairs, worst, expected
+                    );
+                }
+            }
+            OutputMode::Json | OutputMode::JsonLines => {
+                for (attack, pairs, worst) in rows {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(13.0)),
+                            ("family", Value::Str(family)),
+                            ("attack", Value::Str(&attack)),
+                            ("key_seed", Value::Str(&key_seed)),
+                            ("keys", Value::Num(n as f64)),
+                            ("colliding_pairs", Value::Num(pairs)),
+                            ("worst_key_pairs", Value::Num(worst as f64)),
+                            ("random", Value::Num(expected)),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn experiment_13(mode: OutputMode) {
+    let config = (mode, 20);
+    let (n, trials) = (1 << 12, 256);
+
+    if mode.is_csv() {
+        println!("family,attack,keys,colliding_pairs,worst_key_pairs,random");
+    }
+
+    // Multiply-Shift
+
+    {
+        let spec = Spec13 {
+            config,
+            family: "shift",
+            input: (64, n, trials),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.next_odd_u64();
+            let hash = move |l, x| imp::shift_u64(Bits::new(l).unwrap(), a, x);
+            let preimages = move |l, value, n| {
+                adversary::shift_u64_preimages(l, a, value)
+                    .take(n)
+                    .collect()
+            };
+            (hash, preimages)
+        });
+    }
+
+    // Multiply-Mod-Prime
+
+    {
+        let spec = Spec13 {
+            config,
+            family: "mmp-p61",
+            input: (60, n, trials),
+        };
+
+        spec.sample(|rng| {
+            let a = rng.below_m61();
+            let b = rng.below_m61();
+            let hash = move |l, x| imp::mmp_p61_u60_128(Bits::new(l).unwrap(), a, b, x);
+            // Preimages below p, of which those below 2^60 are inputs.
+            let preimages = move |l, value, n| {
+                adversary::mmp_p61_preimages(l, a, b, value)
+                    .filter(|&x| x >> 60 == 0)
+                    .take(n)
+                    .collect()
+            };
+            (hash, preimages)
+        });
+    }
+}
+
+////////////////////////////////////////
+// Experiment 14
+////////////////////////////////////////
+
+// Description: Key recovery. An attacker observes the outputs of a family with
+// m = 2^l for random inputs and solves for the key (see recovery). For each
+// number of observations and each of many keys, the recovered key predicts the
+// outputs for fresh random inputs; the key counts as recovered if all
+// predictions are right. A random guess is right with probability 1/m. Where the
+// key is not recovered, the observations still narrow it: local bits left is the
+// log2 number of keys near the solution that are consistent with them. Keys far
+// from it can be consistent too, so with few observations this is well below the
+// bits the observations leave free.
+// Parameters: attack turns observations (x, h(x)) into a predictor and the local
+// bits left.
+
+pub struct Spec14<'a> {
+    config: (OutputMode, usize),
+    family: &'a str,
+    input: (usize, &'a [usize], u32),
+}
+
+impl<'a> Spec14<'a> {
+    pub fn sample<F, H, A, P>(&self, mut keygen: F, attack: A)
+    where
+        F: FnMut(&mut SplitMix64) -> H,
+        H: Fn(usize, u64) -> u64,
+        A: Fn(usize, &[(u64, u64)]) -> (P, f64),
+        P: Fn(u64) -> u64,
+    {
+        let (mode, l) = self.config;
+        let family = self.family;
+        let (in_bits, counts, trials) = self.input;
+
+        let seed = 0x1f83d9abfb41bd6b;
+        let key_seed = format!("{:#018x}", seed);
+        let mut rng = SplitMix64::new(seed);
+
+        let max = !0 >> (64 - in_bits);
+        let fresh = 1024;
+
+        for &count in counts {
+            let mut recovered = 0;
+            let mut right = 0;
+            let mut local_bits = 0.0;
+            for _ in 0..trials {
+                let hash = keygen(&mut rng);
+                let observations = (0..count)
+                    .map(|_| {
+                        let x = rng.next_u64() & max;
+                        (x, hash(l, x))
+                    })
+                    .collect::<Vec<_>>();
+                let (predict, bits) = attack(l, &observations);
+                let right_here = (0..fresh)
+                    .filter(|_| {
+                        let x = rng.next_u64() & max;
+                        predict(x) == hash(l, x)
+                    })
+                    .count();
+                if right_here == fresh {
+                    recovered += 1;
+                }
+                right += right_here;
+                local_bits += bits;
+            }
+            let accuracy = right as f64 / (fresh * trials as usize) as f64;
+            let local_bits = local_bits / f64::from(trials);
+
+            match mode {
+                OutputMode::Pretty => {
+                    println!(
+                        "Family: {}, observations: {}; key recovered: {}/{}, predictions right: {:.2}%, local bits left: {:.1}",
+                        family,
+                        count,
+                        recovered,
+                        trials,
+                        100.0 * accuracy,
+                        local_bits
+                    );
+                }
+                OutputMode::Csv => {
+                    println!(
+                        "{},{},{},{},{},{}",
+                        family,
+                        count,
+                        trials,
+                        f64::from(recovered) / f64::from(trials),
+                        accuracy,
+                        local_bits
+                    );
+                }
+                OutputMode::Json | OutputMode::JsonLines => {
+                    json::row(
+                        mode,
+                        &[
+                            ("experiment", Value::Num(14.0)),
+                            ("family", Value::Str(family)),
+                            ("bits", Value::Num(l as f64)),
+                            ("key_seed", Value::Str(&key_seed)),
+                            ("observations", Value::Num(count as f64)),
+                            ("trials", Value::Num(f64::from(trials))),
+                            (
+                                "recovered",
+                                Value::Num(f64::from(recovered) / f64::from(trials)),
+                            ),
+                            ("accuracy", Value::Num(accuracy)),
+                            ("local_bits_left", Value::Num(local_bits)),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn experiment_14(mode: OutputMode) {
+    let config = (mode, 20);
+    let counts = [2, 3, 4, 6, 8, 12, 16, 64, 256, 1024, 4096];
+    // With fewer trials the fraction recovered is too noisy to compare across counts.
+    let trials = 64;
+    // Observations beyond these only narrow the key near the lattice solution.
+    let lattice = 16;
+
+    if mode.is_csv() {
+        println!("family,observations,trials,recovered,accuracy,local_bits_left");
+    }
+
+    // Multiply-Shift
+
+    {
+        let spec = Spec14 {
+            config,
+            family: "shift-strong-u32",
+            input: (32, &counts, trials),
+        };
+
+        // a x + b mod 2^64 is in the bucket of width 2^(64 - l) given by the
+        // output.
+        spec.sample(
+            |rng| {
+                let a = rng.next_u64();
+                let b = rng.next_u64();
+                move |l, x| u64::from(imp::shift_strong_u32(Bits::new(l).unwrap(), a, b, x as u32))
+            },
+            |l, observations| {
+                let rows = observations
+                    .iter()
+                    .map(|&(x, h)| (i128::from(x), i128::from(h) << (64 - l), 1 << (64 - l)))
+                    .collect();
+                let observations = recovery::Observations {
+                    modulus: 1 << 64,
+                    rows,
+                };
+                let ((a, b), local_bits) = observations.solve(lattice);
+                let (a, b) = (a as u64, b as u64);
+                let l = Bits::new(l).unwrap();
+                let predict = move |x| u64::from(imp::shift_strong_u32(l, a, b, x as u32));
+                (predict, local_bits)
+            },
+        );
+    }
+
+    // Multiply-Mod-Prime
+
+    {
+        let spec = Spec14 {
+            config,
+            family: "mmp-p31",
+            input: (30, &counts, trials),
+        };
+
+        // With q = (a x + b) mod p and h = q mod 2^l, q = h + 2^l k for some
+        // k < (p - h) / 2^l, so the key (a, b) / 2^l puts (a x + b) / 2^l mod p in
+        // [h / 2^l, h / 2^l + (p - h) / 2^l), where 1 / 2^l = 2^(31 - l) mod p.
+        spec.sample(
+            |rng| {
+                let a = rng.below_m31();
+                let b = rng.below_m31();
+                move |l, x| u64::from(imp::mmp_p31_u30(Bits::new(l).unwrap(), a, b, x as u32))
+            },
+            |l, observations| {
+                let p = i128::from(imp::M31);
+                let rows = observations
+                    .iter()
+                    .map(|&(x, h)| {
+                        let h = i128::from(h);
+                        (
+                            i128::from(x),
+                            (h << (31 - l)) % p,
+                            (p - h + (1 << l) - 1) >> l,
+                        )
+                    })
+                    .collect();
+                let observations = recovery::Observations { modulus: p, rows };
+                let ((a, b), local_bits) = observations.solve(lattice);
+                let key = |x: i128| FieldElem31::new(((x << l) % p) as u32).unwrap();
+                let (a, b) = (key(a), key(b));
+                let l = Bits::new(l).unwrap();
+                let predict = move |x| u64::from(imp::mmp_p31_u30(l, a, b, x as u32));
+                (predict, local_bits)
+            },
+        );
+    }
+
+    // Polynomial
+
+    {
+        let spec = Spec14 {
+            config,
+            family: "poly",
+            input: (64, &counts, trials),
+        };
+
+        // One-word messages, which PolyU64 hashes as ((a x + b) mod p) mod 2^l, so
+        // as for mmp-p31 with 1 / 2^l = 2^(89 - l) mod p. The attack recovers a and
+        // b; c only enters with longer messages.
+        spec.sample(
+            |rng| {
+                let a = rng.below_m89();
+                let b = rng.below_m89();
+                let c = rng.below_m89();
+                move |l, x| {
+                    let mut h = imp::PolyU64::new(a, b, c);
+                    h.write_u64(x);
+                    h.finish(Bits::new(l).unwrap())
+                }
+            },
+            |l, observations| {
+                let p = (1i128 << 89) - 1;
+                let rows = observations
+                    .iter()
+                    .map(|&(x, h)| {
+                        let h = i128::from(h);
+                        (
+                            i128::from(x),
+                            (h << (89 - l)) % p,
+                            (p - h + (1 << l) - 1) >> l,
+                        )
+                    })
+                    .collect();
+                let observations = recovery::Observations { modulus: p, rows };
+                let ((a, b), local_bits) = observations.solve(lattice);
+                let key = |x: i128| {
+                    let x = (x << l) % p;
+                    FieldElem89::new([x as u32, (x >> 32) as u32, (x >> 64) as u32]).unwrap()
+                };
+                let (a, b, c) = (key(a), key(b), key(0));
+                let l = Bits::new(l).unwrap();
+                let predict = move |x| {
+                    let mut h = imp::PolyU64::new(a, b, c);
+                    h.write_u64(x);
+                    h.finish(l)
+                };
+                (predict, local_bits)
+            },
+        );
+    }
+}
@@ -0,0 +1,192 @@
+////////////////////////////////////////
+// MinHash
+////////////////////////////////////////
+
+// Description: MinHash signatures of sets, either with k independent hash functions
+// (k-hash) or with a single hash function split into k bins (one-permutation
+// hashing), where empty bins are filled by optimal densification.
+// Interface: hash functions map an item to a 64-bit value; for one-permutation
+// hashing the bin is taken from the high bits.
+
+// Signature value of a bin that saw no items.
+pub const EMPTY: u64 = !0;
+
+pub enum Scheme<H> {
+    KHash(Vec<H>),
+    OnePermutation(usize, H),
+}
+
+pub struct MinHasher<H> {
+    scheme: Scheme<H>,
+}
+
+impl<H> MinHasher<H> {
+    #[inline]
+    pub fn k_hash(hashes: Vec<H>) -> Self {
+        assert!(!hashes.is_empty());
+        let scheme = Scheme::KHash(hashes);
+        Self { scheme }
+    }
+
+    #[inline]
+    pub fn one_permutation(k: usize, hash: H) -> Self {
+        assert!(k > 0);
+        let scheme = Scheme::OnePermutation(k, hash);
+        Self { scheme }
+    }
+
+    #[inline]
+    pub fn k(&self) -> usize {
+        match self.scheme {
+            Scheme::KHash(ref hashes) => hashes.len(),
+            Scheme::OnePermutation(k, _) => k,
+        }
+    }
+
+    pub fn signature<T>(&self, items: &[T]) -> Vec<u64>
+    where
+        T: Copy,
+        H: Fn(T) -> u64,
+    {
+        match self.scheme {
+            Scheme::KHash(ref hashes) => hashes
+                .iter()
+                .map(|h| items.iter().map(|&x| h(x)).min().unwrap_or(EMPTY))
+                .collect(),
+            Scheme::OnePermutation(k, ref h) => {
+                let mut sig = vec![EMPTY; k];
+                let mut filled = vec![false; k];
+                for &x in items {
+                    let v = h(x);
+                    let bin = ((u128::from(v) * k as u128) >> 64) as usize;
+                    filled[bin] = true;
+                    if v < sig[bin] {
+                        sig[bin] = v;
+                    }
+                }
+                densify(&mut sig, &filled);
+                sig
+            }
+        }
+    }
+}
+
+// Fraction of positions where the two signatures agree.
+pub fn jaccard(a: &[u64], b: &[u64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    let equal = a.iter().zip(b).filter(|&(x, y)| x == y).count();
+    equal as f64 / a.len() as f64
+}
+
+////////////////////////////////////////
+// Helper Functions
+////////////////////////////////////////
+
+// Shrivastava, "Optimal densification for fast and accurate minwise hashing" (2017).
+// Each empty bin copies the first non-empty bin on a probe sequence that depends only
+// on the bin index, so two sets are densified consistently.
+fn densify(sig: &mut [u64], filled: &[bool]) {
+    let k = sig.len();
+    if !filled.iter().any(|&f| f) {
+        return;
+    }
+    for i in 0..k {
+        if filled[i] {
+            continue;
+        }
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let j = (mix(i as u64, attempt) % k as u64) as usize;
+            if filled[j] {
+                sig[i] = sig[j];
+                break;
+            }
+        }
+    }
+}
+
+#[inline]
+fn mix(i: u64, attempt: u64) -> u64 {
+    let mut z = i.wrapping_mul(0x9e3779b97f4a7c15) ^ attempt.wrapping_mul(0xc2b2ae3d27d4eb4f);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use imp::{self, Bits};
+    use rng::SplitMix64;
+
+    fn keygen(rng: &mut SplitMix64) -> impl Fn(u64) -> u64 {
+        let a = rng.below_m89();
+        let b = rng.below_m89();
+        move |x| imp::mmp_p89_u64(Bits::MAX, a, b, x)
+    }
+
+    fn schemes(k: usize, rng: &mut SplitMix64) -> Vec<MinHasher<impl Fn(u64) -> u64>> {
+        vec![
+            MinHasher::k_hash((0..k).map(|_| keygen(rng)).collect()),
+            MinHasher::one_permutation(k, keygen(rng)),
+        ]
+    }
+
+    #[test]
+    fn empty_and_equal_sets() {
+        let mut rng = SplitMix64::new(0x6a09e667f3bcc908);
+        let items = (0..50).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        let mut reversed = items.clone();
+        reversed.reverse();
+
+        for minhash in schemes(64, &mut rng) {
+            assert_eq!(minhash.k(), 64);
+            assert_eq!(minhash.signature::<u64>(&[]), vec![EMPTY; 64]);
+
+            let signature = minhash.signature(&items);
+            assert_eq!(signature, minhash.signature(&reversed));
+            assert_eq!(jaccard(&signature, &signature), 1.0);
+        }
+    }
+
+    #[test]
+    fn densification_fills_every_bin() {
+        // Far fewer items than bins.
+        let mut rng = SplitMix64::new(0xbb67ae8584caa73b);
+        let minhash = MinHasher::one_permutation(256, keygen(&mut rng));
+        for n in 1..10 {
+            let items = (0..n).map(|_| rng.next_u64()).collect::<Vec<_>>();
+            let signature = minhash.signature(&items);
+            assert!(signature.iter().all(|v| *v != EMPTY));
+        }
+    }
+
+    #[test]
+    fn estimate_within_error() {
+        // Standard deviation sqrt(s (1 - s) / k) <= 1 / (2 sqrt(k)); allow four.
+        let k = 256;
+        let tolerance = 4.0 * 0.5 / (k as f64).sqrt();
+        let mut rng = SplitMix64::new(0x3c6ef372fe94f82b);
+        let items = (0..2000).map(|_| rng.next_u64()).collect::<Vec<_>>();
+
+        for minhash in schemes(k, &mut rng) {
+            for &shift in &[0, 100, 500, 1000, 1500] {
+                let a = &items[..1000];
+                let b = &items[shift..shift + 1000 - shift / 2];
+                let union = (shift + b.len()).max(1000) as f64;
+                let exact = (1000 - shift.min(1000)) as f64 / union;
+
+                let estimate = jaccard(&minhash.signature(a), &minhash.signature(b));
+                assert!(
+                    (estimate - exact).abs() <= tolerance,
+                    "shift = {}: {} vs {}",
+                    shift,
+                    estimate,
+                    exact
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+#[cfg(target_os = "linux")]
+use libc;
+
+use json::Value;
+
+////////////////////////////////////////
+// Hardware Performance Counters
+////////////////////////////////////////
+
+// Description: Counts hardware events for the calling thread with perf_event_open
+// (Linux only). Each event is opened on its own, so an event the machine or the
+// container does not allow is reported as missing instead of failing the run.
+// Only user-space events are counted, which works with perf_event_paranoid <= 2.
+
+pub const EVENTS: [&str; 4] = ["instructions", "cycles", "branch_misses", "l1d_misses"];
+
+const JSON_FIELDS: [&str; 4] = [
+    "perf_instructions",
+    "perf_cycles",
+    "perf_branch_misses",
+    "perf_l1d_misses",
+];
+
+pub type Counts = [Option<u64>; 4];
+
+// Runs func and returns the events it caused, or None for every event if disabled.
+pub fn count_if<F, R>(enabled: bool, func: F) -> (R, Option<Counts>)
+where
+    F: FnOnce() -> R,
+{
+    if !enabled {
+        return (func(), None);
+    }
+    let mut counters = Counters::open();
+    counters.start();
+    let value = func();
+    let counts = counters.stop();
+    (value, Some(counts))
+}
+
+// Extra CSV columns, each as events per value; empty if disabled.
+pub fn csv(counts: &Option<Counts>, values: f64) -> String {
+    match *counts {
+        None => String::new(),
+        Some(ref counts) => counts
+            .iter()
+            .map(|count| match *count {
+                Some(count) => format!(",{}", count as f64 / values),
+                None => ",NA".to_string(),
+            })
+            .collect(),
+    }
+}
+
+pub fn csv_header(enabled: bool) -> String {
+    if !enabled {
+        return String::new();
+    }
+    EVENTS
+        .iter()
+        .map(|event| format!(",perf_{}", event))
+        .collect()
+}
+
+// Events per value as JSON fields; empty if disabled.
+pub fn json(counts: &Option<Counts>, values: f64) -> Vec<(&'static str, Value<'static>)> {
+    match *counts {
+        None => Vec::new(),
+        Some(ref counts) => JSON_FIELDS
+            .iter()
+            .zip(counts)
+            .map(|(&field, count)| (field, Value::opt(count.map(|c| c as f64 / values))))
+            .collect(),
+    }
+}
+
+pub fn pretty(counts: &Option<Counts>, values: f64) -> String {
+    match *counts {
+        None => String::new(),
+        Some(ref counts) => {
+            let fields = EVENTS
+                .iter()
+                .zip(counts)
+                .map(|(event, count)| match *count {
+                    Some(count) => format!("{}: {:.3}", event, count as f64 / values),
+                    None => format!("{}: n/a", event),
+                })
+                .collect::<Vec<_>>();
+            format!("; per value: {}", fields.join(", "))
+        }
+    }
+}
+
+////////////////////////////////////////
+// Linux
+////////////////////////////////////////
+
+#[cfg(target_os = "linux")]
+const PERF_TYPE_HARDWARE: u32 = 0;
+#[cfg(target_os = "linux")]
+const PERF_TYPE_HW_CACHE: u32 = 3;
+
+#[cfg(target_os = "linux")]
+const CONFIGS: [(u32, u64); 4] = [
+    // PERF_COUNT_HW_INSTRUCTIONS
+    (PERF_TYPE_HARDWARE, 1),
+    // PERF_COUNT_HW_CPU_CYCLES
+    (PERF_TYPE_HARDWARE, 0),
+    // PERF_COUNT_HW_BRANCH_MISSES
+    (PERF_TYPE_HARDWARE, 5),
+    // L1D | OP_READ << 8 | RESULT_MISS << 16
+    (PERF_TYPE_HW_CACHE, 1 << 16),
+];
+
+#[cfg(target_os = "linux")]
+const FLAG_DISABLED: u64 = 1;
+#[cfg(target_os = "linux")]
+const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+#[cfg(target_os = "linux")]
+const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+#[cfg(target_os = "linux")]
+const IOC_ENABLE: libc::c_ulong = 0x2400;
+#[cfg(target_os = "linux")]
+const IOC_DISABLE: libc::c_ulong = 0x2401;
+#[cfg(target_os = "linux")]
+const IOC_RESET: libc::c_ulong = 0x2403;
+
+// First version of struct perf_event_attr (PERF_ATTR_SIZE_VER0), which every
+// kernel with perf events accepts.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+#[cfg(target_os = "linux")]
+pub struct Counters {
+    fds: [Option<libc::c_int>; 4],
+}
+
+#[cfg(target_os = "linux")]
+impl Counters {
+    pub fn open() -> Self {
+        let mut fds = [None; 4];
+        for (fd, &(kind, config)) in fds.iter_mut().zip(&CONFIGS) {
+            let attr = PerfEventAttr {
+                kind,
+                size: 64,
+                config,
+                flags: FLAG_DISABLED | FLAG_EXCLUDE_KERNEL | FLAG_EXCLUDE_HV,
+                ..Default::default()
+            };
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_perf_event_open,
+                    &attr as *const PerfEventAttr,
+                    0 as libc::pid_t,
+                    -1 as libc::c_int,
+                    -1 as libc::c_int,
+                    0 as libc::c_ulong,
+                )
+            };
+            if ret >= 0 {
+                *fd = Some(ret as libc::c_int);
+            }
+        }
+        Self { fds }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.fds.iter().any(|fd| fd.is_some())
+    }
+
+    pub fn start(&mut self) {
+        for fd in self.fds.iter().filter_map(|&fd| fd) {
+            unsafe {
+                libc::ioctl(fd, IOC_RESET, 0);
+                libc::ioctl(fd, IOC_ENABLE, 0);
+            }
+        }
+    }
+
+    pub fn stop(&mut self) -> Counts {
+        let mut counts = [None; 4];
+        for (count, fd) in counts.iter_mut().zip(&self.fds) {
+            if let Some(fd) = *fd {
+                let mut value = 0u64;
+                let read = unsafe {
+                    libc::ioctl(fd, IOC_DISABLE, 0);
+                    libc::read(fd, &mut value as *mut u64 as *mut libc::c_void, 8)
+                };
+                if read == 8 {
+                    *count = Some(value);
+                }
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Counters {
+    fn drop(&mut self) {
+        for fd in self.fds.iter().filter_map(|&fd| fd) {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+    }
+}
+
+////////////////////////////////////////
+// Other Platforms
+////////////////////////////////////////
+
+#[cfg(not(target_os = "linux"))]
+pub struct Counters;
+
+#[cfg(not(target_os = "linux"))]
+impl Counters {
+    pub fn open() -> Self {
+        Counters
+    }
+
+    pub fn is_available(&self) -> bool {
+        false
+    }
+
+    pub fn start(&mut self) {}
+
+    pub fn stop(&mut self) -> Counts {
+        [None; 4]
+    }
+}
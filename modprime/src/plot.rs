@@ -0,0 +1,462 @@
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::{self, Write as IoWrite};
+use std::path::Path;
+
+use table::{invalid, Table};
+
+////////////////////////////////////////
+// Plots
+////////////////////////////////////////
+
+// Description: Renders the charts of plots.R as SVG without R. Experiment 1 gives
+// time per value against input bit-length, one color per scheme and one shape per
+// is128: all schemes up to 128 bits (plot1), the fast schemes (plot2) and the
+// long-input schemes (plot3). Experiment 2 gives time per byte per family (plot4).
+// The y axis starts at zero and is scaled to the data instead of the fixed limits
+// of plots.R, so points are never dropped on faster or slower machines.
+
+const FAST_SCHEMES: [&str; 4] = ["mmp", "mmp-triple", "shift", "shift-strong"];
+
+// Reads experiment1.csv and experiment2.csv from input_dir and writes the plots
+// to output_dir. A missing CSV file skips its plots; returns the files written.
+pub fn plot_all(input_dir: &str, output_dir: &str) -> io::Result<Vec<String>> {
+    let mut charts = Vec::new();
+
+    if let Some(table) = read_optional(&Path::new(input_dir).join("experiment1.csv"))? {
+        let rows = experiment_1_rows(&table)?;
+        let fast = |row: &Row1| FAST_SCHEMES.contains(&&row.scheme[..]);
+        let unit = time_unit(&table)?;
+        let name = format!("time per value [{}]", unit);
+        charts.push((
+            "plot1.svg",
+            chart_1(&rows, &name, 128.0, 32.0, |row| row.bits <= 128.0),
+        ));
+        charts.push(("plot2.svg", chart_1(&rows, &name, 128.0, 32.0, fast)));
+        charts.push((
+            "plot3.svg",
+            chart_1(&rows, &name, 3072.0, 1024.0, |row| !fast(row)),
+        ));
+    }
+
+    if let Some(table) = read_optional(&Path::new(input_dir).join("experiment2.csv"))? {
+        let unit = time_unit(&table)?;
+        charts.push((
+            "plot4.svg",
+            chart_2(&table, &format!("time per byte [{}]", unit))?,
+        ));
+    }
+
+    fs::create_dir_all(output_dir)?;
+    let mut written = Vec::new();
+    for (file, chart) in charts {
+        let path = Path::new(output_dir).join(file);
+        File::create(&path)?.write_all(chart.render().as_bytes())?;
+        written.push(path.display().to_string());
+    }
+    Ok(written)
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<Table>> {
+    match Table::read(&path.to_string_lossy()) {
+        Ok(table) => Ok(Some(table)),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(io::Error::new(
+            err.kind(),
+            format!("{}: {}", path.display(), err),
+        )),
+    }
+}
+
+fn time_unit(table: &Table) -> io::Result<&'static str> {
+    match (table.column("nanos"), table.column("cycles")) {
+        (Some(_), _) => Ok("ns"),
+        (_, Some(_)) => Ok("cycles"),
+        _ => Err(invalid("no nanos or cycles column")),
+    }
+}
+
+fn time_column(table: &Table) -> usize {
+    table
+        .column("nanos")
+        .or_else(|| table.column("cycles"))
+        .unwrap()
+}
+
+////////////////////////////////////////
+// Experiment Charts
+////////////////////////////////////////
+
+struct Row1 {
+    scheme: String,
+    bits: f64,
+    is_128: bool,
+    time: f64,
+}
+
+fn experiment_1_rows(table: &Table) -> io::Result<Vec<Row1>> {
+    let column = |name| {
+        table
+            .column(name)
+            .ok_or_else(|| invalid(&format!("no {} column", name)))
+    };
+    let (scheme, bits, is_128) = (column("scheme")?, column("bits")?, column("is128")?);
+    let time = time_column(table);
+
+    table
+        .rows
+        .iter()
+        .map(|row| {
+            Ok(Row1 {
+                scheme: row[scheme].clone(),
+                bits: Table::number(row, bits)?,
+                is_128: row[is_128].eq_ignore_ascii_case("true"),
+                time: Table::number(row, time)?,
+            })
+        })
+        .collect()
+}
+
+fn chart_1<F>(rows: &[Row1], y_name: &str, x_max: f64, x_step: f64, filter: F) -> Chart
+where
+    F: Fn(&Row1) -> bool,
+{
+    let rows = rows.iter().filter(|row| filter(row)).collect::<Vec<_>>();
+
+    let mut groups = Vec::<String>::new();
+    for row in &rows {
+        if !groups.contains(&row.scheme) {
+            groups.push(row.scheme.clone());
+        }
+    }
+
+    let points = rows
+        .iter()
+        .filter(|row| row.bits <= x_max)
+        .map(|row| Point {
+            x: row.bits,
+            y: row.time,
+            group: groups.iter().position(|g| *g == row.scheme),
+            shape: if row.is_128 {
+                Shape::Triangle
+            } else {
+                Shape::Circle
+            },
+        })
+        .collect::<Vec<_>>();
+
+    let ticks = (0..)
+        .map(|i| f64::from(i) * x_step)
+        .take_while(|&x| x <= x_max)
+        .map(|x| (x, format!("{}", x)))
+        .collect();
+
+    Chart {
+        x: Axis {
+            name: "bit-length of input".to_string(),
+            limits: (0.0, x_max),
+            ticks,
+        },
+        y: y_axis(y_name, &points),
+        groups: Some(("scheme".to_string(), groups)),
+        shapes: Some("is128".to_string()),
+        points,
+    }
+}
+
+fn chart_2(table: &Table, y_name: &str) -> io::Result<Chart> {
+    let family = table
+        .column("family")
+        .ok_or_else(|| invalid("no family column"))?;
+    let time = time_column(table);
+
+    let mut points = Vec::new();
+    let mut ticks = Vec::new();
+    for (i, row) in table.rows.iter().enumerate() {
+        let x = i as f64 + 1.0;
+        ticks.push((x, row[family].clone()));
+        points.push(Point {
+            x,
+            y: Table::number(row, time)?,
+            group: None,
+            shape: Shape::Circle,
+        });
+    }
+
+    Ok(Chart {
+        x: Axis {
+            name: "family".to_string(),
+            limits: (0.4, table.rows.len() as f64 + 0.6),
+            ticks,
+        },
+        y: y_axis(y_name, &points),
+        groups: None,
+        shapes: None,
+        points,
+    })
+}
+
+// From zero to a round number above the data, with ticks at 1, 2 or 5 times a
+// power of ten.
+fn y_axis(name: &str, points: &[Point]) -> Axis {
+    let max = points.iter().map(|p| p.y).fold(0.0, f64::max);
+    let max = if max > 0.0 { max } else { 1.0 };
+    let magnitude = 10f64.powf((max / 5.0).log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&step| max / step <= 6.0)
+        .unwrap();
+    let top = (max / step).ceil() * step;
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+
+    let ticks = (0..)
+        .map(|i| f64::from(i) * step)
+        .take_while(|&y| y <= top + step / 2.0)
+        .map(|y| (y, format!("{:.*}", decimals, y)))
+        .collect();
+
+    Axis {
+        name: name.to_string(),
+        limits: (0.0, top),
+        ticks,
+    }
+}
+
+////////////////////////////////////////
+// SVG Rendering
+////////////////////////////////////////
+
+const WIDTH: f64 = 720.0;
+const HEIGHT: f64 = 450.0;
+const LEFT: f64 = 70.0;
+const RIGHT: f64 = 150.0;
+const TOP: f64 = 20.0;
+const BOTTOM: f64 = 55.0;
+
+struct Axis {
+    name: String,
+    limits: (f64, f64),
+    ticks: Vec<(f64, String)>,
+}
+
+#[derive(Clone, Copy)]
+enum Shape {
+    Circle,
+    Triangle,
+}
+
+struct Point {
+    x: f64,
+    y: f64,
+    group: Option<usize>,
+    shape: Shape,
+}
+
+struct Chart {
+    x: Axis,
+    y: Axis,
+    // Legend title and names of the color groups.
+    groups: Option<(String, Vec<String>)>,
+    // Legend title of the shapes, FALSE as circles and TRUE as triangles.
+    shapes: Option<String>,
+    points: Vec<Point>,
+}
+
+impl Chart {
+    fn render(&self) -> String {
+        let (x0, x1) = (LEFT, WIDTH - RIGHT);
+        let (y0, y1) = (HEIGHT - BOTTOM, TOP);
+        let sx =
+            |x: f64| x0 + (x - self.x.limits.0) / (self.x.limits.1 - self.x.limits.0) * (x1 - x0);
+        let sy =
+            |y: f64| y0 + (y - self.y.limits.0) / (self.y.limits.1 - self.y.limits.0) * (y1 - y0);
+
+        let colors = match self.groups {
+            Some((_, ref groups)) => hue_palette(groups.len()),
+            None => Vec::new(),
+        };
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             font-family=\"sans-serif\" font-size=\"11\">",
+            WIDTH, HEIGHT
+        );
+        let _ = writeln!(
+            svg,
+            "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>",
+            WIDTH, HEIGHT
+        );
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#ebebeb\"/>",
+            x0,
+            y1,
+            x1 - x0,
+            y0 - y1
+        );
+
+        // Grid lines and tick labels.
+        for &(x, ref label) in &self.x.ticks {
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{0:.1}\" y1=\"{1}\" x2=\"{0:.1}\" y2=\"{2}\" stroke=\"white\"/>",
+                sx(x),
+                y0,
+                y1
+            );
+            let _ = writeln!(
+                svg,
+                "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\" fill=\"#4d4d4d\">{}</text>",
+                sx(x),
+                y0 + 15.0,
+                escape(label)
+            );
+        }
+        for &(y, ref label) in &self.y.ticks {
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{0}\" y1=\"{1:.1}\" x2=\"{2}\" y2=\"{1:.1}\" stroke=\"white\"/>",
+                x0,
+                sy(y),
+                x1
+            );
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\" fill=\"#4d4d4d\">{}</text>",
+                x0 - 5.0,
+                sy(y) + 4.0,
+                escape(label)
+            );
+        }
+
+        // Axis titles.
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"13\">{}</text>",
+            (x0 + x1) / 2.0,
+            HEIGHT - 15.0,
+            escape(&self.x.name)
+        );
+        let _ = writeln!(
+            svg,
+            "<text transform=\"translate({},{}) rotate(-90)\" text-anchor=\"middle\" \
+             font-size=\"13\">{}</text>",
+            20.0,
+            (y0 + y1) / 2.0,
+            escape(&self.y.name)
+        );
+
+        for point in &self.points {
+            let color = point.group.map_or("black", |g| &colors[g][..]);
+            svg.push_str(&marker(point.shape, sx(point.x), sy(point.y), color));
+        }
+
+        // Legends.
+        let mut y = TOP + 10.0;
+        let x = x1 + 15.0;
+        if let Some((ref title, ref groups)) = self.groups {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"13\">{}</text>",
+                x,
+                y,
+                escape(title)
+            );
+            for (group, color) in groups.iter().zip(&colors) {
+                y += 18.0;
+                svg.push_str(&marker(Shape::Circle, x + 6.0, y - 4.0, color));
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{}\" y=\"{}\">{}</text>",
+                    x + 18.0,
+                    y,
+                    escape(group)
+                );
+            }
+            y += 30.0;
+        }
+        if let Some(ref title) = self.shapes {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"13\">{}</text>",
+                x,
+                y,
+                escape(title)
+            );
+            for &(shape, label) in &[(Shape::Circle, "FALSE"), (Shape::Triangle, "TRUE")] {
+                y += 18.0;
+                svg.push_str(&marker(shape, x + 6.0, y - 4.0, "black"));
+                let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\">{}</text>", x + 18.0, y, label);
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn marker(shape: Shape, x: f64, y: f64, color: &str) -> String {
+    match shape {
+        Shape::Circle => format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3.5\" fill=\"{}\"/>\n",
+            x, y, color
+        ),
+        Shape::Triangle => format!(
+            "<polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"{}\"/>\n",
+            x,
+            y - 4.5,
+            x - 4.0,
+            y + 3.0,
+            x + 4.0,
+            y + 3.0,
+            color
+        ),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// The default discrete colors of ggplot2: n hues evenly spaced from 15 degrees at
+// chroma 100 and luminance 65 in HCL, converted to sRGB.
+fn hue_palette(n: usize) -> Vec<String> {
+    let (xn, yn, zn) = (95.047, 100.0, 108.883);
+    let un = 4.0 * xn / (xn + 15.0 * yn + 3.0 * zn);
+    let vn = 9.0 * yn / (xn + 15.0 * yn + 3.0 * zn);
+
+    (0..n)
+        .map(|i| {
+            let (l, c) = (65.0f64, 100.0f64);
+            let h = (15.0 + 360.0 * i as f64 / n as f64).to_radians();
+            let (u, v) = (c * h.cos(), c * h.sin());
+
+            let y = yn * ((l + 16.0) / 116.0).powi(3);
+            let u = u / (13.0 * l) + un;
+            let v = v / (13.0 * l) + vn;
+            let x = y * 9.0 * u / (4.0 * v);
+            let z = y * (12.0 - 3.0 * u - 20.0 * v) / (4.0 * v);
+            let (x, y, z) = (x / 100.0, y / 100.0, z / 100.0);
+
+            let rgb = [
+                3.2406 * x - 1.5372 * y - 0.4986 * z,
+                -0.9689 * x + 1.8758 * y + 0.0415 * z,
+                0.0557 * x - 0.2040 * y + 1.0570 * z,
+            ];
+            let [r, g, b] = rgb.map(|c| {
+                let c = if c <= 0.003_130_8 {
+                    12.92 * c
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                };
+                (c.clamp(0.0, 1.0) * 255.0).round() as u8
+            });
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        })
+        .collect()
+}
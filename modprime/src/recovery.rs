@@ -0,0 +1,221 @@
+////////////////////////////////////////
+// Key Recovery
+////////////////////////////////////////
+
+// Description: Recovers the key (a, b) of a x + b mod N from observations that
+// each confine a x + b mod N to a known range [t, t + w), which is what an
+// observer of hash values learns about the key of a linear family: the known
+// bits of each output fix a x + b mod N up to the bits the output drops. The
+// key makes a lattice vector close to the range centers, which LLL reduction
+// followed by Babai's nearest plane finds once there are enough observations for
+// it to be the closest. The lattice basis is kept exact in i128; only the
+// Gram-Schmidt data is in f64.
+//
+// The closest vector in the Euclidean norm need not be inside all ranges, and
+// not all bits of the key need be determined: shifting b moves all of a x + b
+// alike, and for x < 2^k, shifting a by less than N / 2^k moves them little.
+// So the lattice solution is only a starting point. The keys (a + s, b + d) near
+// it that keep every observation in range are those with s in an interval and d
+// in a range that depends on s, and their center is the best guess.
+// Interface: N < 2^90, so that the lattice basis fits in i128.
+
+pub struct Observations {
+    pub modulus: i128,
+    // Per observation, x < 2^64 and the range [t, t + w) of a x + b mod N as
+    // (x, t, w), with t < N and 0 < w < N / 2.
+    pub rows: Vec<(i128, i128, i128)>,
+}
+
+impl Observations {
+    // The key and the log2 number of keys near it consistent with all
+    // observations, from the lattice on the first rows observations. The count is
+    // local: it covers a within N / (2 max x) of the lattice solution, and keys
+    // farther away may be consistent too.
+    pub fn solve(&self, rows: usize) -> ((i128, i128), f64) {
+        let n = self.modulus;
+        let (a, b) = self.closest(rows.min(self.rows.len()));
+
+        // Offsets (x, c, w) that put s x + d in [c, c + w).
+        let offsets = self
+            .rows
+            .iter()
+            .map(|&(x, t, w)| {
+                let c = (t - mul_mod(a, x, n) - b).rem_euclid(n);
+                (x, if c > n / 2 { c - n } else { c }, w)
+            })
+            .collect::<Vec<_>>();
+        // The range of d for s.
+        let range = |s: i128| {
+            offsets
+                .iter()
+                .fold((i128::MIN, i128::MAX), |(lo, hi), &(x, c, w)| {
+                    (lo.max(c - s * x), hi.min(c - s * x + w))
+                })
+        };
+        let slack = |s: i128| {
+            let (lo, hi) = range(s);
+            hi - lo
+        };
+
+        // The slack is concave in s, so the s with positive slack form an
+        // interval around its maximum.
+        let max_x = self.rows.iter().map(|&(x, _, _)| x).max().unwrap_or(0);
+        let reach = n / (2 * max_x.max(1));
+        let (mut lo, mut hi) = (-reach, reach);
+        while hi - lo > 2 {
+            let (m1, m2) = (lo + (hi - lo) / 3, hi - (hi - lo) / 3);
+            if slack(m1) < slack(m2) {
+                lo = m1 + 1;
+            } else {
+                hi = m2;
+            }
+        }
+        let best = (lo..=hi).max_by_key(|&s| slack(s)).unwrap();
+        if slack(best) <= 0 {
+            return ((a, b), 2.0 * (n as f64).log2());
+        }
+        let first = partition(-reach, best, |s| slack(s) > 0);
+        let last = partition(best, reach + 1, |s| slack(s) <= 0) - 1;
+
+        // The count sums the slack over at most 2^12 values of s.
+        let step = ((last - first) >> 12) + 1;
+        let count = (first..=last)
+            .step_by(step as usize)
+            .map(|s| (slack(s) * step) as f64)
+            .sum::<f64>();
+
+        let s = first + (last - first) / 2;
+        let (lo, hi) = range(s);
+        let d = lo + (hi - lo) / 2;
+        (((a + s).rem_euclid(n), (b + d).rem_euclid(n)), count.log2())
+    }
+
+    // The key of the lattice vector closest to the range centers.
+    fn closest(&self, rows: usize) -> (i128, i128) {
+        let n = self.modulus;
+        let rows = &self.rows[..rows];
+        let k = rows.len();
+
+        // Observation coordinates are scaled by v so that their errors and the
+        // key are both about N.
+        let bound = rows.iter().map(|&(_, _, w)| w / 2).max().unwrap_or(1);
+        let v = n / bound.max(1);
+        let dim = k + 2;
+
+        let mut basis = Vec::with_capacity(dim);
+        for j in 0..2 {
+            let mut row = vec![0; dim];
+            for (i, &(x, _, _)) in rows.iter().enumerate() {
+                row[i] = v * if j == 0 { x % n } else { 1 };
+            }
+            row[k + j] = 1;
+            basis.push(row);
+        }
+        for i in 0..k {
+            let mut row = vec![0; dim];
+            row[i] = v * n;
+            basis.push(row);
+        }
+
+        let mut target = vec![n / 2; dim];
+        for (i, &(_, t, w)) in rows.iter().enumerate() {
+            target[i] = v * ((t + w / 2) % n);
+        }
+
+        lll(&mut basis);
+        let u = babai(&basis, &target);
+        (u[k].rem_euclid(n), u[k + 1].rem_euclid(n))
+    }
+}
+
+// The first i in [lo, hi) with pred(i), if pred is monotone, or hi.
+fn partition<P>(mut lo: i128, mut hi: i128, pred: P) -> i128
+where
+    P: Fn(i128) -> bool,
+{
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    lo
+}
+
+// a x mod n, in two halves of x.
+// Interface: a < n < 2^90, x < 2^64
+pub fn mul_mod(a: i128, x: i128, n: i128) -> i128 {
+    let high = a * (x >> 32) % n;
+    ((high << 32) % n + a * (x & 0xffffffff)) % n
+}
+
+fn dot(x: &[f64], y: &[f64]) -> f64 {
+    x.iter().zip(y).map(|(x, y)| x * y).sum()
+}
+
+fn to_f64(x: &[i128]) -> Vec<f64> {
+    x.iter().map(|&x| x as f64).collect()
+}
+
+// Gram-Schmidt vectors of the rows from start on, given those before.
+fn gram_schmidt(basis: &[Vec<i128>], star: &mut Vec<Vec<f64>>, start: usize) {
+    star.truncate(start);
+    for row in &basis[start..] {
+        let mut v = to_f64(row);
+        for s in star.iter() {
+            let mu = dot(&v, s) / dot(s, s);
+            for (v, s) in v.iter_mut().zip(s) {
+                *v -= mu * s;
+            }
+        }
+        star.push(v);
+    }
+}
+
+// LLL reduction with delta = 0.99.
+pub fn lll(basis: &mut [Vec<i128>]) {
+    let mut star = Vec::new();
+    gram_schmidt(basis, &mut star, 0);
+
+    let mut k = 1;
+    while k < basis.len() {
+        for j in (0..k).rev() {
+            let q = (dot(&to_f64(&basis[k]), &star[j]) / dot(&star[j], &star[j])).round();
+            if q != 0.0 {
+                let q = q as i128;
+                let (head, tail) = basis.split_at_mut(k);
+                for (x, &y) in tail[0].iter_mut().zip(&head[j]) {
+                    *x -= q * y;
+                }
+            }
+        }
+
+        let mu = dot(&to_f64(&basis[k]), &star[k - 1]) / dot(&star[k - 1], &star[k - 1]);
+        let norm = dot(&star[k], &star[k]);
+        let prev = dot(&star[k - 1], &star[k - 1]);
+        if norm >= (0.99 - mu * mu) * prev {
+            k += 1;
+        } else {
+            basis.swap(k, k - 1);
+            gram_schmidt(basis, &mut star, k - 1);
+            k = (k - 1).max(1);
+        }
+    }
+}
+
+// The lattice vector of Babai's nearest plane to target.
+pub fn babai(basis: &[Vec<i128>], target: &[i128]) -> Vec<i128> {
+    let mut star = Vec::new();
+    gram_schmidt(basis, &mut star, 0);
+
+    let mut rest = target.to_vec();
+    for (row, s) in basis.iter().zip(&star).rev() {
+        let q = (dot(&to_f64(&rest), s) / dot(s, s)).round() as i128;
+        for (x, &y) in rest.iter_mut().zip(row) {
+            *x -= q * y;
+        }
+    }
+    target.iter().zip(&rest).map(|(t, r)| t - r).collect()
+}
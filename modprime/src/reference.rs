@@ -0,0 +1,337 @@
+use std::cmp::Ordering;
+
+use imp::{M31, M61, M89};
+
+////////////////////////////////////////
+// Arbitrary-Precision Integers
+////////////////////////////////////////
+
+// Description: Unsigned integers of any size as little-endian 32-bit limbs, with
+// schoolbook multiplication and bitwise long division. Slow, but simple enough to
+// check by eye, which is the point: the reference implementations below are
+// written with it in the textbook form of each family.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    pub fn from_u64(x: u64) -> Self {
+        Self::from_limbs(&[x as u32, (x >> 32) as u32])
+    }
+
+    pub fn from_u128(x: u128) -> Self {
+        Self::from_limbs(&[
+            x as u32,
+            (x >> 32) as u32,
+            (x >> 64) as u32,
+            (x >> 96) as u32,
+        ])
+    }
+
+    pub fn from_limbs(limbs: &[u32]) -> Self {
+        let mut n = Self {
+            limbs: limbs.to_vec(),
+        };
+        n.normalize();
+        n
+    }
+
+    // 2^k
+    pub fn pow2(k: usize) -> Self {
+        let mut limbs = vec![0; k / 32 + 1];
+        limbs[k / 32] = 1 << (k % 32);
+        Self { limbs }
+    }
+
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(&top) => 32 * self.limbs.len() - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn bit(&self, i: usize) -> bool {
+        self.limbs
+            .get(i / 32)
+            .is_some_and(|&limb| limb >> (i % 32) & 1 == 1)
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0u64;
+        for i in 0..len {
+            let sum = u64::from(self.limb(i)) + u64::from(other.limb(i)) + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        Self::from_limbs(&limbs)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let prod = u64::from(a) * u64::from(b) + u64::from(limbs[i + j]) + carry;
+                limbs[i + j] = prod as u32;
+                carry = prod >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        Self::from_limbs(&limbs)
+    }
+
+    // By bitwise long division, shifting into a fixed-size remainder in place.
+    pub fn rem(&self, m: &Self) -> Self {
+        assert!(*m != Self::zero());
+        let mut r = vec![0u32; m.limbs.len() + 1];
+        let m = m.limbs_padded(r.len());
+        for i in (0..self.bits()).rev() {
+            let mut carry = u32::from(self.bit(i));
+            for limb in r.iter_mut() {
+                let top = *limb >> 31;
+                *limb = *limb << 1 | carry;
+                carry = top;
+            }
+            if r.iter().rev().cmp(m.iter().rev()) != Ordering::Less {
+                let mut borrow = 0i64;
+                for (limb, &m) in r.iter_mut().zip(&m) {
+                    let diff = i64::from(*limb) - i64::from(m) - borrow;
+                    *limb = diff as u32;
+                    borrow = if diff < 0 { 1 } else { 0 };
+                }
+            }
+        }
+        Self::from_limbs(&r)
+    }
+
+    // self div 2^k, bit by bit.
+    pub fn shr(&self, k: usize) -> Self {
+        let bits = self.bits().saturating_sub(k);
+        let mut limbs = vec![0u32; bits / 32 + 1];
+        for i in 0..bits {
+            if self.bit(i + k) {
+                limbs[i / 32] |= 1 << (i % 32);
+            }
+        }
+        Self::from_limbs(&limbs)
+    }
+
+    // self mod 2^64
+    pub fn low_u64(&self) -> u64 {
+        u64::from(self.limb(0)) | u64::from(self.limb(1)) << 32
+    }
+
+    // self mod 2^128
+    pub fn low_u128(&self) -> u128 {
+        (0..4).fold(0, |x, i| x | u128::from(self.limb(i)) << (32 * i))
+    }
+
+    fn limbs_padded(&self, len: usize) -> Vec<u32> {
+        (0..len).map(|i| self.limb(i)).collect()
+    }
+
+    fn limb(&self, i: usize) -> u32 {
+        self.limbs.get(i).cloned().unwrap_or(0)
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+////////////////////////////////////////
+// Reference Implementations
+////////////////////////////////////////
+
+// Description: Each family as its textbook definition, to test the word and limb
+// arithmetic of imp against. Parameters and interfaces are those of imp, with the
+// keys as plain integers.
+
+pub fn p89() -> BigUint {
+    BigUint::from_limbs(&M89)
+}
+
+fn mask(l: usize) -> u64 {
+    !0 >> (64 - l)
+}
+
+// ((a x + b) mod 2^w) div 2^(w - l)
+fn shift(w: usize, l: usize, a: &BigUint, b: &BigUint, x: &BigUint) -> BigUint {
+    a.mul(x).add(b).rem(&BigUint::pow2(w)).shr(w - l)
+}
+
+pub fn shift_u32(l: usize, a: u32, x: u32) -> u32 {
+    let (a, x) = (BigUint::from_u64(a.into()), BigUint::from_u64(x.into()));
+    shift(32, l, &a, &BigUint::zero(), &x).low_u64() as u32
+}
+
+pub fn shift_u64(l: usize, a: u64, x: u64) -> u64 {
+    let (a, x) = (BigUint::from_u64(a), BigUint::from_u64(x));
+    shift(64, l, &a, &BigUint::zero(), &x).low_u64()
+}
+
+pub fn shift_u128_128(l: usize, a: u128, x: u128) -> u128 {
+    let (a, x) = (BigUint::from_u128(a), BigUint::from_u128(x));
+    shift(128, l, &a, &BigUint::zero(), &x).low_u128()
+}
+
+pub fn shift_strong_u32(l: usize, a: u64, b: u64, x: u32) -> u32 {
+    let (a, b) = (BigUint::from_u64(a), BigUint::from_u64(b));
+    shift(64, l, &a, &b, &BigUint::from_u64(x.into())).low_u64() as u32
+}
+
+pub fn shift_strong_u64_128(l: usize, a: u128, b: u128, x: u64) -> u64 {
+    let (a, b) = (BigUint::from_u128(a), BigUint::from_u128(b));
+    shift(128, l, &a, &b, &BigUint::from_u64(x)).low_u64()
+}
+
+// ((a x + b) mod p) mod 2^l
+fn mmp(p: &BigUint, l: usize, a: u64, b: u64, x: u64) -> u64 {
+    let (a, b) = (BigUint::from_u64(a), BigUint::from_u64(b));
+    a.mul(&BigUint::from_u64(x)).add(&b).rem(p).low_u64() & mask(l)
+}
+
+pub fn mmp_p31_u30(l: usize, a: u32, b: u32, x: u32) -> u32 {
+    let p = BigUint::from_u64(M31.into());
+    mmp(&p, l, a.into(), b.into(), x.into()) as u32
+}
+
+// Xor of mmp_p31_u30 over the 30-bit pieces (x div 2^(30 i)) mod 2^30.
+pub fn mmp_p31_u64(l: usize, a: [u32; 3], b: [u32; 3], x: u64) -> u32 {
+    let x = BigUint::from_u64(x);
+    (0..3).fold(0, |q, i| {
+        let piece = x.shr(30 * i).rem(&BigUint::pow2(30)).low_u64() as u32;
+        q ^ mmp_p31_u30(l, a[i], b[i], piece)
+    })
+}
+
+pub fn mmp_p61_u60(l: usize, a: u64, b: u64, x: u64) -> u64 {
+    mmp(&BigUint::from_u64(M61), l, a, b, x)
+}
+
+// ((a x + b) mod p) mod 2^l
+
+pub fn mmp_p89_u64(l: usize, a: [u32; 3], b: [u32; 3], x: u64) -> u64 {
+    let a = BigUint::from_limbs(&a);
+    let b = BigUint::from_limbs(&b);
+    let x = BigUint::from_u64(x);
+    a.mul(&x).add(&b).rem(&p89()).low_u64() & mask(l)
+}
+
+// The message as a polynomial in c with coefficients mod p, by Horner's rule.
+fn poly_p89(c: [u32; 3], xs: &[u64]) -> BigUint {
+    let p = p89();
+    let c = BigUint::from_limbs(&c);
+    xs.iter().fold(BigUint::zero(), |state, &x| {
+        state.mul(&c).add(&BigUint::from_u64(x)).rem(&p)
+    })
+}
+
+// ((a poly_c(xs) + b) mod p) mod 2^l
+pub fn poly_u64(l: usize, a: [u32; 3], b: [u32; 3], c: [u32; 3], xs: &[u64]) -> u64 {
+    let a = BigUint::from_limbs(&a);
+    let b = BigUint::from_limbs(&b);
+    let state = poly_p89(c, xs);
+    a.mul(&state).add(&b).rem(&p89()).low_u64() & mask(l)
+}
+
+// Xor of strong multiply-shift on the three 32-bit limbs of poly_c(xs).
+pub fn poly_shift_u64(l: usize, a: [u64; 3], b: [u64; 3], c: [u32; 3], xs: &[u64]) -> u32 {
+    let state = poly_p89(c, xs);
+    (0..3).fold(0, |q, i| {
+        let limb = BigUint::from_u64(u64::from(state.limb(i)));
+        let h = BigUint::from_u64(a[i])
+            .mul(&limb)
+            .add(&BigUint::from_u64(b[i]))
+            .low_u64();
+        q ^ (h >> (64 - l)) as u32
+    })
+}
+
+// (a[n] + sum of a[i] x_i) mod 2^64 over a message of n <= 64 words, top l bits.
+pub fn vector_shift_u32(l: usize, a: &[u64; 65], xs: &[u32]) -> u32 {
+    let sum = xs
+        .iter()
+        .enumerate()
+        .fold(BigUint::from_u64(a[xs.len()]), |sum, (i, &x)| {
+            sum.add(&BigUint::from_u64(a[i]).mul(&BigUint::from_u64(x.into())))
+        });
+    shift(64, l, &sum, &BigUint::zero(), &BigUint::from_u64(1)).low_u64() as u32
+}
+
+// (a[2n] + sum of (a[2i] + hi(x_i)) (a[2i+1] + lo(x_i))) mod 2^64 over a message
+// of n <= 32 words, with the factors mod 2^64, top l bits.
+pub fn pair_shift_u64(l: usize, a: &[u64; 65], xs: &[u64]) -> u32 {
+    let u = BigUint::pow2(64);
+    let sum = xs
+        .iter()
+        .enumerate()
+        .fold(BigUint::from_u64(a[2 * xs.len()]), |sum, (i, &x)| {
+            let f0 = BigUint::from_u64(a[2 * i]).add(&BigUint::from_u64(x >> 32));
+            let f1 = BigUint::from_u64(a[2 * i + 1]).add(&BigUint::from_u64(x & 0xffffffff));
+            sum.add(&f0.rem(&u).mul(&f1.rem(&u)))
+        });
+    shift(64, l, &sum, &BigUint::zero(), &BigUint::from_u64(1)).low_u64() as u32
+}
+
+// Xor of t[i][(x div 2^(8 i)) mod 2^8] over the 8 bytes of x, top l bits.
+pub fn tabulation_u64(l: usize, t: &[[u64; 256]; 8], x: u64) -> u64 {
+    let x = BigUint::from_u64(x);
+    let h = (0..8).fold(0, |h, i| {
+        let byte = x.shr(8 * i).rem(&BigUint::pow2(8)).low_u64();
+        h ^ t[i][byte as usize]
+    });
+    h >> (64 - l)
+}
+
+// poly_u64 of the pair-shift hashes of the 32-word blocks of xs. An empty message
+// is one empty block.
+pub fn preproc_poly_u64(
+    l: usize,
+    prep: (&[u64; 65], &[u64; 65]),
+    a: [u32; 3],
+    b: [u32; 3],
+    c: [u32; 3],
+    xs: &[u64],
+) -> u64 {
+    let blocks = if xs.is_empty() {
+        vec![xs]
+    } else {
+        xs.chunks(32).collect()
+    };
+    let ys = blocks
+        .iter()
+        .map(|block| {
+            let q1 = pair_shift_u64(32, prep.0, block);
+            let q2 = pair_shift_u64(32, prep.1, block);
+            u64::from(q1) | u64::from(q2) << 32
+        })
+        .collect::<Vec<_>>();
+    poly_u64(l, a, b, c, &ys)
+}
@@ -0,0 +1,66 @@
+use compare::Baseline;
+
+////////////////////////////////////////
+// Benchmark Registry
+////////////////////////////////////////
+
+// Description: Selects benchmark cases by name. Cases are named "scheme/bits" in
+// experiment 1 (e.g. "shift/64", "poly/1024") and by family in experiment 2
+// (e.g. "preproc-poly"). A case runs if it matches any filter (or there are none)
+// and no exclude. In list mode cases print their name instead of running; with a
+// baseline, cases that run are compared against it instead of printing a row.
+// Interface: patterns are globs where * matches any run of characters and ? any
+// single character.
+
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    pub list: bool,
+    pub filters: Vec<String>,
+    pub excludes: Vec<String>,
+    pub baseline: Option<Baseline>,
+}
+
+impl Registry {
+    pub fn is_selected(&self, name: &str) -> bool {
+        let included = self.filters.is_empty() || self.filters.iter().any(|f| glob(f, name));
+        let excluded = self.excludes.iter().any(|e| glob(e, name));
+        included && !excluded
+    }
+
+    // Whether the case should run; listing a selected case prints its name instead.
+    pub fn visit(&self, name: &str) -> bool {
+        if !self.is_selected(name) {
+            return false;
+        }
+        if self.list {
+            println!("{}", name);
+            return false;
+        }
+        true
+    }
+}
+
+pub fn glob(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let name = name.as_bytes();
+
+    // Backtrack to the last star on a mismatch.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((sp, sn)) = star {
+            p = sp + 1;
+            n = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
@@ -0,0 +1,94 @@
+use imp::{FieldElem31, FieldElem61, FieldElem89, OddU64, M31, M61, M89};
+
+////////////////////////////////////////
+// SplitMix64
+////////////////////////////////////////
+
+// Description: Small deterministic generator used to draw keys for experiments.
+// Not suitable for anything where the keys have to stay secret.
+
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    #[inline]
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline]
+    pub fn next_u128(&mut self) -> u128 {
+        (u128::from(self.next_u64()) << 64) | u128::from(self.next_u64())
+    }
+
+    // Uniform in [0, 1).
+    #[inline]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Uniform in [0, bound), bound > 0.
+    #[inline]
+    pub fn below_u64(&mut self, bound: u64) -> u64 {
+        debug_assert!(bound > 0);
+        let zone = bound.wrapping_neg() % bound;
+        loop {
+            let x = self.next_u64();
+            if x >= zone {
+                return x % bound;
+            }
+        }
+    }
+
+    // Uniform odd.
+    #[inline]
+    pub fn next_odd_u64(&mut self) -> OddU64 {
+        OddU64::new(self.next_u64() | 1).unwrap()
+    }
+
+    // Uniform in [0, 2^31 - 1).
+    #[inline]
+    pub fn below_m31(&mut self) -> FieldElem31 {
+        FieldElem31::new(self.below_u64(u64::from(M31)) as u32).unwrap()
+    }
+
+    // Uniform in [0, 2^61 - 1).
+    #[inline]
+    pub fn below_m61(&mut self) -> FieldElem61 {
+        FieldElem61::new(self.below_u64(M61)).unwrap()
+    }
+
+    // Uniform in [0, 2^89 - 1).
+    #[inline]
+    pub fn below_m89(&mut self) -> FieldElem89 {
+        loop {
+            let x = [self.next_u32(), self.next_u32(), self.next_u32() & M89[2]];
+            if let Some(x) = FieldElem89::new(x) {
+                return x;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn fill_u64(&mut self, dst: &mut [u64]) {
+        for x in dst {
+            *x = self.next_u64();
+        }
+    }
+}
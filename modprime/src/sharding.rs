@@ -0,0 +1,177 @@
+////////////////////////////////////////
+// Consistent Hashing
+////////////////////////////////////////
+
+// Description: Ring of virtual nodes. Every node owns the arcs ending at the
+// positions hash(node << 32 | replica), and a key goes to the first position at or
+// after its hash, wrapping around.
+// Interface: key hashes and positions are 64-bit.
+// Parameters: replicas >= 1, node < 2^32
+
+pub struct ConsistentRing<H> {
+    hash: H,
+    replicas: usize,
+    ring: Vec<(u64, usize)>,
+}
+
+impl<H> ConsistentRing<H>
+where
+    H: Fn(u64) -> u64,
+{
+    pub fn new(nodes: usize, replicas: usize, hash: H) -> Self {
+        assert!(replicas > 0);
+        let mut ring = Self {
+            hash,
+            replicas,
+            ring: Vec::with_capacity(nodes * replicas),
+        };
+        for node in 0..nodes {
+            ring.add_node(node);
+        }
+        ring
+    }
+
+    pub fn add_node(&mut self, node: usize) {
+        debug_assert!((node as u64) >> 32 == 0);
+        for replica in 0..self.replicas {
+            let point = (self.hash)((node as u64) << 32 | replica as u64);
+            let i = match self.ring.binary_search(&(point, node)) {
+                Ok(i) | Err(i) => i,
+            };
+            self.ring.insert(i, (point, node));
+        }
+    }
+
+    pub fn remove_node(&mut self, node: usize) {
+        self.ring.retain(|&(_, n)| n != node);
+    }
+
+    #[inline]
+    pub fn node(&self, key_hash: u64) -> usize {
+        assert!(!self.ring.is_empty());
+        let i = match self.ring.binary_search(&(key_hash, 0)) {
+            Ok(i) | Err(i) => i,
+        };
+        self.ring[i % self.ring.len()].1
+    }
+}
+
+////////////////////////////////////////
+// Jump Consistent Hash
+////////////////////////////////////////
+
+// Description: Lamping and Veach, "A fast, minimal memory, consistent hash
+// algorithm" (2014). The key hash seeds the internal linear congruential generator.
+// Interface: buckets >= 1
+
+#[inline]
+pub fn jump_hash(key_hash: u64, buckets: u32) -> u32 {
+    assert!(buckets > 0);
+    let mut key = key_hash;
+    let mut b = -1i64;
+    let mut j = 0i64;
+    while j < i64::from(buckets) {
+        b = j;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    b as u32
+}
+
+////////////////////////////////////////
+// Rendezvous Hashing
+////////////////////////////////////////
+
+// Description: Highest random weight. Each node scores the key with
+// hash(key_hash ^ salt(node)) and the key goes to the highest score.
+// Interface: nodes >= 1
+
+#[inline]
+pub fn rendezvous<H>(key_hash: u64, nodes: usize, hash: H) -> usize
+where
+    H: Fn(u64) -> u64,
+{
+    assert!(nodes > 0);
+    (0..nodes)
+        .max_by_key(|&node| (hash(key_hash ^ salt(node)), node))
+        .unwrap()
+}
+
+#[inline]
+fn salt(node: usize) -> u64 {
+    (node as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use imp::{self, Bits};
+    use rng::SplitMix64;
+
+    fn keys(seed: u64) -> Vec<u64> {
+        let mut rng = SplitMix64::new(seed);
+        (0..10_000).map(|_| rng.next_u64()).collect()
+    }
+
+    fn keygen(rng: &mut SplitMix64) -> impl Fn(u64) -> u64 {
+        let a = rng.below_m89();
+        let b = rng.below_m89();
+        move |x| imp::mmp_p89_u64(Bits::MAX, a, b, x)
+    }
+
+    #[test]
+    fn ring_moves_only_removed_keys() {
+        let mut rng = SplitMix64::new(0x428a2f98d728ae22);
+        let mut ring = ConsistentRing::new(10, 40, keygen(&mut rng));
+        let keys = keys(0x7137449123ef65cd);
+        let before = keys.iter().map(|&k| ring.node(k)).collect::<Vec<_>>();
+        assert!(before.iter().all(|&node| node < 10));
+
+        ring.remove_node(3);
+        for (&k, &node) in keys.iter().zip(&before) {
+            if node == 3 {
+                assert_ne!(ring.node(k), 3);
+            } else {
+                assert_eq!(ring.node(k), node);
+            }
+        }
+
+        ring.add_node(3);
+        let after = keys.iter().map(|&k| ring.node(k)).collect::<Vec<_>>();
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn jump_moves_keys_to_new_bucket() {
+        let keys = keys(0xb5c0fbcfec4d3b2f);
+        for buckets in 1..50 {
+            for &k in &keys {
+                let old = jump_hash(k, buckets);
+                let new = jump_hash(k, buckets + 1);
+                assert!(old < buckets);
+                assert!(new == old || new == buckets);
+            }
+        }
+        // Close to balanced: 10000 / 7 keys per bucket, within 20%.
+        let mut load = [0; 7];
+        for &k in &keys {
+            load[jump_hash(k, 7) as usize] += 1;
+        }
+        assert!(load
+            .iter()
+            .all(|&n| (n as f64 - 10_000.0 / 7.0).abs() < 0.2 * 10_000.0 / 7.0));
+    }
+
+    #[test]
+    fn rendezvous_moves_keys_to_new_node() {
+        let mut rng = SplitMix64::new(0xe9b5dba58189dbbc);
+        let hash = keygen(&mut rng);
+        for &k in &keys(0x3956c25bf348b538) {
+            let old = rendezvous(k, 8, &hash);
+            let new = rendezvous(k, 9, &hash);
+            assert!(old < 8);
+            assert!(new == old || new == 8);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs::File;
+use std::io::{self, Read};
+
+////////////////////////////////////////
+// CSV Tables
+////////////////////////////////////////
+
+// Description: A CSV file as written by the experiments in CSV mode: a header line
+// followed by rows of plain comma-separated fields, without quoting. Blank lines
+// are skipped.
+
+#[derive(Clone, Debug)]
+pub struct Table {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn read(path: &str) -> io::Result<Self> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+
+        let mut lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split(',')
+                    .map(|field| field.trim().to_string())
+                    .collect::<Vec<_>>()
+            });
+        let header = lines.next().ok_or_else(|| invalid("empty file"))?;
+        let rows = lines.collect::<Vec<_>>();
+        if rows.iter().any(|row| row.len() < header.len()) {
+            return Err(invalid("short row"));
+        }
+
+        Ok(Self { header, rows })
+    }
+
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.header.iter().position(|column| column == name)
+    }
+
+    pub fn number(row: &[String], column: usize) -> io::Result<f64> {
+        row[column]
+            .parse()
+            .map_err(|_| invalid(&format!("malformed number {:?}", row[column])))
+    }
+}
+
+pub fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
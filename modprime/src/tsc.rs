@@ -0,0 +1,89 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{__cpuid, __rdtscp, _mm_lfence, _rdtsc};
+use std::time::{Duration, Instant};
+
+////////////////////////////////////////
+// Time Stamp Counter
+////////////////////////////////////////
+
+// Description: Timing with the x86-64 time stamp counter. Reads are fenced so that
+// the measured code can neither start before the first read nor be pending after
+// the second. On CPUs with an invariant TSC the counter ticks at a constant
+// reference rate, so "cycles" are reference cycles rather than core clock cycles
+// when frequency scaling is active.
+
+// Whether rdtscp and an invariant TSC are available.
+#[cfg(target_arch = "x86_64")]
+pub fn is_available() -> bool {
+    let max = __cpuid(0x80000000).eax;
+    if max < 0x80000007 {
+        return false;
+    }
+    let rdtscp = __cpuid(0x80000001).edx & (1 << 27) != 0;
+    let invariant = __cpuid(0x80000007).edx & (1 << 8) != 0;
+    rdtscp && invariant
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn is_available() -> bool {
+    false
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+pub fn start() -> u64 {
+    unsafe {
+        _mm_lfence();
+        let t = _rdtsc();
+        _mm_lfence();
+        t
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+pub fn stop() -> u64 {
+    unsafe {
+        let mut aux = 0;
+        let t = __rdtscp(&mut aux);
+        _mm_lfence();
+        t
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn start() -> u64 {
+    unreachable!("TSC timing requires x86-64")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn stop() -> u64 {
+    unreachable!("TSC timing requires x86-64")
+}
+
+pub fn time_ticks_one<F>(mut func: F) -> f64
+where
+    F: FnMut(),
+{
+    let begin = start();
+    func();
+    let end = stop();
+    end.wrapping_sub(begin) as f64
+}
+
+// Ticks per nanosecond, as the median of several 20 ms spins against Instant.
+pub fn calibrate() -> f64 {
+    let mut rates = (0..5)
+        .map(|_| {
+            let instant = Instant::now();
+            let begin = start();
+            while instant.elapsed() < Duration::from_millis(20) {}
+            let end = stop();
+            let elapsed = instant.elapsed();
+            let nanos = elapsed.as_secs() as f64 * 1e9 + elapsed.subsec_nanos() as f64;
+            end.wrapping_sub(begin) as f64 / nanos
+        })
+        .collect::<Vec<_>>();
+    rates.sort_by(f64::total_cmp);
+    rates[rates.len() / 2]
+}